@@ -0,0 +1,60 @@
+use std::{
+    env, fs,
+    io::{self, Write},
+    process::{Command, Stdio},
+    time::Instant,
+};
+
+const DEFAULT_SIZE_MB: usize = 256;
+
+// Run with `cargo bench`, optionally setting CATR_BENCH_MB to change the
+// size of the generated input.
+fn main() -> io::Result<()> {
+    let size_mb = env::var("CATR_BENCH_MB")
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(DEFAULT_SIZE_MB);
+    let input = env::temp_dir().join(format!("catr-bench-{}.txt", std::process::id()));
+    write_fixture(&input, size_mb)?;
+    let input = input.to_str().unwrap().to_string();
+
+    let output = env::temp_dir().join(format!("catr-bench-{}.out", std::process::id()));
+    for (label, args, to_file) in [
+        ("file to file", vec![input.as_str()], true),
+        ("file to pipe", vec![input.as_str()], false),
+        ("numbered lines", vec!["-n", input.as_str()], false),
+    ] {
+        let stdout = if to_file {
+            Stdio::from(fs::File::create(&output)?)
+        } else {
+            Stdio::piped()
+        };
+        let start = Instant::now();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_catr"))
+            .args(&args)
+            .stdout(stdout)
+            .spawn()?;
+        if let Some(mut pipe) = child.stdout.take() {
+            io::copy(&mut pipe, &mut io::sink())?;
+        }
+        assert!(child.wait()?.success());
+        let seconds = start.elapsed().as_secs_f64();
+        println!(
+            "{label:>16}: {size_mb} MB in {seconds:.3}s ({:.0} MB/s)",
+            size_mb as f64 / seconds
+        );
+    }
+
+    fs::remove_file(&input)?;
+    fs::remove_file(&output)?;
+    Ok(())
+}
+
+fn write_fixture(path: &std::path::Path, size_mb: usize) -> io::Result<()> {
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    let a_line = b"The quick brown fox jumps over the lazy dog, again and again.\n";
+    for _ in 0..size_mb * 1024 * 1024 / a_line.len() {
+        file.write_all(a_line)?;
+    }
+    file.flush()
+}
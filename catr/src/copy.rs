@@ -0,0 +1,113 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+#[cfg(target_os = "linux")]
+use std::os::fd::AsRawFd;
+
+const BUFFER_SIZE: usize = 128 * 1024;
+
+/// Copies everything left in `input` to stdout, inside the kernel when the
+/// file descriptors allow it. `out` must write to stdout, it is flushed
+/// first.
+#[cfg(target_os = "linux")]
+pub(crate) fn copy_to_stdout<R: Read + AsRawFd>(
+    input: &mut R,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    out.flush()?;
+    let out_fd = io::stdout().as_raw_fd();
+    for a_syscall in [Syscall::CopyFileRange, Syscall::Sendfile, Syscall::Splice] {
+        if let Some(result) = a_syscall.copy(input.as_raw_fd(), out_fd) {
+            return result.map(|_| ());
+        }
+    }
+    buffered_copy(input, out).map(|_| ())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn copy_to_stdout<R: Read>(input: &mut R, out: &mut dyn Write) -> io::Result<()> {
+    buffered_copy(input, out).map(|_| ())
+}
+
+/// Returns the last byte copied, if any. Flushes `out` after every read, so
+/// that input coming in slowly, as from a terminal, comes out as soon as it
+/// is read.
+pub(crate) fn buffered_copy<R: Read>(input: &mut R, out: &mut dyn Write) -> io::Result<Option<u8>> {
+    let mut reader = BufReader::with_capacity(BUFFER_SIZE, input);
+    let mut last = None;
+    loop {
+        let chunk = match reader.fill_buf() {
+            Ok(chunk) => chunk,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let Some(&chunk_last) = chunk.last() else {
+            return Ok(last);
+        };
+        out.write_all(chunk)?;
+        out.flush()?;
+        let len = chunk.len();
+        reader.consume(len);
+        last = Some(chunk_last);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy)]
+enum Syscall {
+    CopyFileRange,
+    Sendfile,
+    Splice,
+}
+#[cfg(target_os = "linux")]
+impl Syscall {
+    const CHUNK_SIZE: usize = 1 << 30;
+
+    // Returns `None` when this syscall cannot move data between these two
+    // descriptors and nothing was copied yet, so the next one can be tried.
+    fn copy(self, in_fd: i32, out_fd: i32) -> Option<io::Result<u64>> {
+        let mut copied = 0;
+        loop {
+            match self.copy_chunk(in_fd, out_fd) {
+                0 => return Some(Ok(copied)),
+                n if n > 0 => copied += n as u64,
+                _ => {
+                    let e = io::Error::last_os_error();
+                    match e.raw_os_error() {
+                        Some(libc::EINTR) => continue,
+                        Some(
+                            libc::EINVAL
+                            | libc::ENOSYS
+                            | libc::EXDEV
+                            | libc::EOPNOTSUPP
+                            | libc::EBADF
+                            | libc::ESPIPE
+                            | libc::EPERM,
+                        ) if copied == 0 => return None,
+                        _ => return Some(Err(e)),
+                    }
+                }
+            }
+        }
+    }
+
+    fn copy_chunk(self, in_fd: i32, out_fd: i32) -> isize {
+        let null = std::ptr::null_mut();
+        // SAFETY: both descriptors stay open for the whole call and null
+        // offsets make the kernel use and advance the file positions.
+        unsafe {
+            match self {
+                Syscall::CopyFileRange => {
+                    libc::copy_file_range(in_fd, null, out_fd, null, Self::CHUNK_SIZE, 0) as isize
+                }
+                Syscall::Sendfile => libc::sendfile(out_fd, in_fd, null, Self::CHUNK_SIZE),
+                Syscall::Splice => libc::splice(
+                    in_fd,
+                    null,
+                    out_fd,
+                    null,
+                    Self::CHUNK_SIZE,
+                    libc::SPLICE_F_MOVE,
+                ),
+            }
+        }
+    }
+}
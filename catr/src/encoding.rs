@@ -0,0 +1,159 @@
+use std::io::{self, BufRead, Read};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Bom {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+impl Bom {
+    pub fn bytes(&self) -> &'static [u8] {
+        match self {
+            Bom::Utf8 => &[0xef, 0xbb, 0xbf],
+            Bom::Utf16Le => &[0xff, 0xfe],
+            Bom::Utf16Be => &[0xfe, 0xff],
+        }
+    }
+}
+
+/// Streams UTF-16 input as UTF-8.
+pub(crate) struct Utf16Decoder<R> {
+    input: R,
+    big_endian: bool,
+    pending: Vec<u8>,
+    offset: u64,
+    out: Vec<u8>,
+    pos: usize,
+}
+impl<R: Read> Utf16Decoder<R> {
+    /// `input` is read past its byte order mark.
+    pub fn new(input: R, big_endian: bool) -> Self {
+        Utf16Decoder {
+            input,
+            big_endian,
+            pending: Vec::new(),
+            offset: 2,
+            out: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.input
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        self.out.clear();
+        self.pos = 0;
+        let mut chunk = vec![0; CHUNK_SIZE];
+        while self.out.is_empty() {
+            let n = self.input.read(&mut chunk)?;
+            if n == 0 {
+                return match self.pending.len() {
+                    0 => Ok(()),
+                    1 => Err(invalid(format!(
+                        "invalid UTF-16: odd number of bytes, last one at byte {}",
+                        self.offset
+                    ))),
+                    _ => Err(self.unpaired(self.unit(0))),
+                };
+            }
+            self.pending.extend_from_slice(&chunk[..n]);
+            self.decode_pending()?;
+        }
+        Ok(())
+    }
+
+    fn decode_pending(&mut self) -> io::Result<()> {
+        let mut decoded = 0;
+        while decoded + 2 <= self.pending.len() {
+            let unit = self.unit(decoded);
+            let (c, len) = match unit {
+                0xd800..=0xdbff => {
+                    if decoded + 4 > self.pending.len() {
+                        break;
+                    }
+                    let low = self.unit(decoded + 2);
+                    if !(0xdc00..=0xdfff).contains(&low) {
+                        return Err(self.unpaired(unit));
+                    }
+                    let c = 0x10000 + ((unit as u32 - 0xd800) << 10) + (low as u32 - 0xdc00);
+                    (char::from_u32(c).unwrap(), 4)
+                }
+                0xdc00..=0xdfff => return Err(self.unpaired(unit)),
+                _ => (char::from_u32(unit as u32).unwrap(), 2),
+            };
+            let mut buffer = [0; 4];
+            self.out
+                .extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            decoded += len;
+            self.offset += len as u64;
+        }
+        self.pending.drain(..decoded);
+        Ok(())
+    }
+
+    fn unit(&self, at: usize) -> u16 {
+        let bytes = [self.pending[at], self.pending[at + 1]];
+        if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    }
+
+    fn unpaired(&self, a_unit: u16) -> io::Error {
+        invalid(format!(
+            "invalid UTF-16: unpaired surrogate {a_unit:#06x} at byte {}",
+            self.offset
+        ))
+    }
+}
+impl<R: Read> Read for Utf16Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.fill_buf()?.len());
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+impl<R: Read> BufRead for Utf16Decoder<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.out.len() {
+            self.fill()?;
+        }
+        Ok(&self.out[self.pos..])
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.pos += n;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+impl LineEnding {
+    pub fn normalize(&self, a_raw_line: &mut Vec<u8>) {
+        match self {
+            LineEnding::Lf => {
+                if a_raw_line.ends_with(b"\r\n") {
+                    a_raw_line.remove(a_raw_line.len() - 2);
+                }
+            }
+            LineEnding::Crlf => {
+                if a_raw_line.ends_with(b"\n") && !a_raw_line.ends_with(b"\r\n") {
+                    a_raw_line.insert(a_raw_line.len() - 1, b'\r');
+                }
+            }
+        }
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
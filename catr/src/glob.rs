@@ -0,0 +1,111 @@
+#[derive(Debug, Clone)]
+enum Token {
+    Char(char),
+    AnyChar,
+    AnyString,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Char(expected) => *expected == c,
+            Token::AnyChar => true,
+            Token::AnyString => false,
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|(low, high)| (*low..=*high).contains(&c)) != *negated
+            }
+        }
+    }
+}
+
+/// A shell wildcard pattern: `*`, `?`, `[...]` classes with ranges and `!`
+/// or `^` negation, and `\` escapes.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    tokens: Vec<Token>,
+}
+impl Glob {
+    pub fn parse(a_pattern: &str) -> Result<Glob, String> {
+        let mut tokens = Vec::new();
+        let mut chars = a_pattern.chars();
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                '*' => Token::AnyString,
+                '?' => Token::AnyChar,
+                '[' => Self::parse_class(&mut chars)
+                    .ok_or(format!("unclosed [ in pattern \"{a_pattern}\""))?,
+                '\\' => Token::Char(chars.next().unwrap_or('\\')),
+                c => Token::Char(c),
+            });
+        }
+        Ok(Glob { tokens })
+    }
+
+    fn parse_class(chars: &mut std::str::Chars) -> Option<Token> {
+        let mut negated = false;
+        let mut ranges = Vec::new();
+        let mut c = chars.next()?;
+        if c == '!' || c == '^' {
+            negated = true;
+            c = chars.next()?;
+        }
+        loop {
+            let low = c;
+            c = chars.next()?;
+            if c == '-' {
+                match chars.next()? {
+                    ']' => {
+                        ranges.extend([(low, low), ('-', '-')]);
+                        break;
+                    }
+                    high => {
+                        ranges.push((low, high));
+                        c = chars.next()?;
+                    }
+                }
+            } else {
+                ranges.push((low, low));
+            }
+            if c == ']' {
+                break;
+            }
+        }
+        Some(Token::Class { negated, ranges })
+    }
+
+    pub fn matches(&self, a_name: &str) -> bool {
+        let name: Vec<char> = a_name.chars().collect();
+        let (mut t, mut n) = (0, 0);
+        // Where to resume after the last `*` if the rest does not match.
+        let mut backtrack = None;
+        while n < name.len() {
+            match self.tokens.get(t) {
+                Some(Token::AnyString) => {
+                    backtrack = Some((t, n));
+                    t += 1;
+                    continue;
+                }
+                Some(token) if token.matches(name[n]) => {
+                    t += 1;
+                    n += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            match backtrack {
+                Some((star, start)) => {
+                    backtrack = Some((star, start + 1));
+                    t = star + 1;
+                    n = start + 1;
+                }
+                None => return false,
+            }
+        }
+        self.tokens[t..]
+            .iter()
+            .all(|token| matches!(token, Token::AnyString))
+    }
+}
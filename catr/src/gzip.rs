@@ -0,0 +1,478 @@
+use std::io::{self, BufRead, BufReader, Read};
+
+pub(crate) const MAGIC: [u8; 2] = [0x1f, 0x8b];
+const DEFLATE: u8 = 8;
+const WINDOW_SIZE: usize = 32 * 1024;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+const FRESERVED: u8 = 0xe0;
+
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+enum State {
+    Header,
+    Block,
+    Stored(usize),
+    Codes(Huffman, Huffman),
+    Trailer,
+    Done,
+}
+
+/// Streams the decompressed contents of one or more concatenated gzip
+/// members (RFC 1952) holding DEFLATE data (RFC 1951).
+pub(crate) struct GzipDecoder<R> {
+    input: BitReader<R>,
+    state: State,
+    last_block: bool,
+    out: Vec<u8>,
+    pos: usize,
+    checked: usize,
+    crc: u32,
+    member_len: u64,
+}
+impl<R: Read> GzipDecoder<R> {
+    pub fn new(input: R) -> Self {
+        GzipDecoder {
+            input: BitReader::new(input),
+            state: State::Header,
+            last_block: false,
+            out: Vec::with_capacity(WINDOW_SIZE + CHUNK_SIZE),
+            pos: 0,
+            checked: 0,
+            crc: 0,
+            member_len: 0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        self.input.input.get_ref()
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        self.discard_consumed();
+        while self.out.len() - self.pos < CHUNK_SIZE {
+            self.state = match std::mem::replace(&mut self.state, State::Done) {
+                State::Header => {
+                    self.read_header()?;
+                    State::Block
+                }
+                State::Block => self.read_block_header()?,
+                State::Stored(0) => self.end_of_block(),
+                State::Stored(remaining) => State::Stored(remaining - self.copy_stored(remaining)?),
+                State::Codes(literals, distances) => {
+                    if self.inflate(&literals, &distances)? {
+                        self.end_of_block()
+                    } else {
+                        State::Codes(literals, distances)
+                    }
+                }
+                State::Trailer => {
+                    self.check_trailer()?;
+                    if self.input.at_end()? {
+                        State::Done
+                    } else {
+                        State::Header
+                    }
+                }
+                State::Done => break,
+            };
+        }
+        Ok(())
+    }
+
+    // Drops output already handed out, keeping the window that later
+    // back-references may reach.
+    fn discard_consumed(&mut self) {
+        self.update_crc();
+        let discarded = self.pos.min(self.out.len().saturating_sub(WINDOW_SIZE));
+        if discarded >= CHUNK_SIZE {
+            self.out.drain(..discarded);
+            self.pos -= discarded;
+            self.checked -= discarded;
+        }
+    }
+
+    fn update_crc(&mut self) {
+        self.crc = crc32(self.crc, &self.out[self.checked..]);
+        self.checked = self.out.len();
+    }
+
+    fn read_header(&mut self) -> io::Result<()> {
+        if [self.input.byte()?, self.input.byte()?] != MAGIC {
+            return Err(corrupt("not in gzip format"));
+        }
+        if self.input.byte()? != DEFLATE {
+            return Err(corrupt("unknown compression method"));
+        }
+        let flags = self.input.byte()?;
+        if flags & FRESERVED != 0 {
+            return Err(corrupt("reserved header flags set"));
+        }
+        // MTIME, XFL and OS
+        self.input.skip(6)?;
+        if flags & FEXTRA != 0 {
+            let extra_len = self.input.u16()?;
+            self.input.skip(extra_len as usize)?;
+        }
+        for flag in [FNAME, FCOMMENT] {
+            if flags & flag != 0 {
+                while self.input.byte()? != 0 {}
+            }
+        }
+        if flags & FHCRC != 0 {
+            self.input.skip(2)?;
+        }
+        self.update_crc();
+        self.crc = 0;
+        self.member_len = 0;
+        Ok(())
+    }
+
+    fn read_block_header(&mut self) -> io::Result<State> {
+        self.last_block = self.input.bits(1)? == 1;
+        match self.input.bits(2)? {
+            0 => {
+                self.input.align();
+                let len = self.input.u16()?;
+                if len != !self.input.u16()? {
+                    return Err(corrupt("stored block length mismatch"));
+                }
+                Ok(State::Stored(len as usize))
+            }
+            1 => {
+                let mut lengths = [8; 288];
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                Ok(State::Codes(
+                    Huffman::new(&lengths)?,
+                    Huffman::new(&[5; 30])?,
+                ))
+            }
+            2 => self.read_dynamic_codes(),
+            _ => Err(corrupt("invalid block type")),
+        }
+    }
+
+    fn read_dynamic_codes(&mut self) -> io::Result<State> {
+        let num_literals = self.input.bits(5)? as usize + 257;
+        let num_distances = self.input.bits(5)? as usize + 1;
+        let num_code_lengths = self.input.bits(4)? as usize + 4;
+        if num_literals > 286 || num_distances > 30 {
+            return Err(corrupt("too many length or distance codes"));
+        }
+        let mut code_lengths = [0; 19];
+        for index in &CODE_LENGTH_ORDER[..num_code_lengths] {
+            code_lengths[*index] = self.input.bits(3)? as u8;
+        }
+        let code_lengths = Huffman::new(&code_lengths)?;
+        let mut lengths = Vec::with_capacity(num_literals + num_distances);
+        while lengths.len() < num_literals + num_distances {
+            let (length, repeat) = match code_lengths.decode(&mut self.input)? {
+                symbol @ 0..=15 => (symbol as u8, 1),
+                16 => {
+                    let previous = lengths
+                        .last()
+                        .ok_or(corrupt("length repeated with no first length"))?;
+                    (*previous, 3 + self.input.bits(2)?)
+                }
+                17 => (0, 3 + self.input.bits(3)?),
+                _ => (0, 11 + self.input.bits(7)?),
+            };
+            lengths.extend(std::iter::repeat_n(length, repeat as usize));
+        }
+        if lengths.len() > num_literals + num_distances {
+            return Err(corrupt("too many code lengths"));
+        }
+        if lengths[256] == 0 {
+            return Err(corrupt("missing end-of-block code"));
+        }
+        Ok(State::Codes(
+            Huffman::new(&lengths[..num_literals])?,
+            Huffman::new(&lengths[num_literals..])?,
+        ))
+    }
+
+    fn end_of_block(&self) -> State {
+        if self.last_block {
+            State::Trailer
+        } else {
+            State::Block
+        }
+    }
+
+    fn copy_stored(&mut self, remaining: usize) -> io::Result<usize> {
+        let bytes = self.input.buffered()?;
+        let copied = bytes.len().min(remaining);
+        self.out.extend_from_slice(&bytes[..copied]);
+        self.input.consume(copied);
+        self.member_len += copied as u64;
+        Ok(copied)
+    }
+
+    // Returns whether the end of the block was reached, rather than a full
+    // chunk of output.
+    fn inflate(&mut self, literals: &Huffman, distances: &Huffman) -> io::Result<bool> {
+        while self.out.len() - self.pos < CHUNK_SIZE {
+            let symbol = literals.decode(&mut self.input)? as usize;
+            match symbol {
+                0..=255 => {
+                    self.out.push(symbol as u8);
+                    self.member_len += 1;
+                }
+                256 => return Ok(true),
+                257..=285 => {
+                    let index = symbol - 257;
+                    let len = LENGTH_BASES[index] as usize
+                        + self.input.bits(LENGTH_EXTRA_BITS[index])? as usize;
+                    let index = distances.decode(&mut self.input)? as usize;
+                    if index >= DISTANCE_BASES.len() {
+                        return Err(corrupt("invalid distance code"));
+                    }
+                    let distance = DISTANCE_BASES[index] as usize
+                        + self.input.bits(DISTANCE_EXTRA_BITS[index])? as usize;
+                    if distance as u64 > self.member_len {
+                        return Err(corrupt("invalid distance too far back"));
+                    }
+                    let start = self.out.len() - distance;
+                    for offset in 0..len {
+                        self.out.push(self.out[start + offset]);
+                    }
+                    self.member_len += len as u64;
+                }
+                _ => return Err(corrupt("invalid literal/length code")),
+            }
+        }
+        Ok(false)
+    }
+
+    fn check_trailer(&mut self) -> io::Result<()> {
+        self.input.align();
+        let crc = self.input.u32()?;
+        let size = self.input.u32()?;
+        self.update_crc();
+        if crc != self.crc {
+            return Err(corrupt("CRC32 mismatch"));
+        }
+        if size != self.member_len as u32 {
+            return Err(corrupt("length mismatch"));
+        }
+        Ok(())
+    }
+}
+impl<R: Read> Read for GzipDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.fill_buf()?.len());
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+impl<R: Read> BufRead for GzipDecoder<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.out.len() {
+            self.fill()?;
+        }
+        Ok(&self.out[self.pos..])
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.pos += n;
+    }
+}
+
+/// Reads DEFLATE's least significant bit first bit stream.
+struct BitReader<R> {
+    input: BufReader<R>,
+    bits: u32,
+    count: u8,
+}
+impl<R: Read> BitReader<R> {
+    fn new(input: R) -> Self {
+        BitReader {
+            input: BufReader::new(input),
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn bits(&mut self, n: u8) -> io::Result<u32> {
+        while self.count < n {
+            self.bits |= (self.byte()? as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.bits & ((1 << n) - 1);
+        self.bits >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// Drops the bits left in the current byte.
+    fn align(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        let byte = *self.buffered()?.first().unwrap();
+        self.input.consume(1);
+        Ok(byte)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes([self.byte()?, self.byte()?]))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes([
+            self.byte()?,
+            self.byte()?,
+            self.byte()?,
+            self.byte()?,
+        ]))
+    }
+
+    fn skip(&mut self, n: usize) -> io::Result<()> {
+        for _ in 0..n {
+            self.byte()?;
+        }
+        Ok(())
+    }
+
+    /// Whole bytes waiting in the input, never empty.
+    fn buffered(&mut self) -> io::Result<&[u8]> {
+        match self.input.fill_buf()? {
+            [] => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "gzip: unexpected end of input, file truncated",
+            )),
+            bytes => Ok(bytes),
+        }
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.input.consume(n);
+    }
+
+    fn at_end(&mut self) -> io::Result<bool> {
+        Ok(self.input.fill_buf()?.is_empty())
+    }
+}
+
+/// A canonical Huffman code, decoded a bit at a time.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Self> {
+        let mut counts = [0; 16];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        let mut left = 1;
+        for count in &counts[1..] {
+            left = 2 * left - *count as i32;
+            if left < 0 {
+                return Err(corrupt("over-subscribed Huffman code"));
+            }
+        }
+        let mut offsets = [0; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, length) in lengths.iter().enumerate() {
+            if *length != 0 {
+                symbols[offsets[*length as usize] as usize] = symbol as u16;
+                offsets[*length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode<R: Read>(&self, input: &mut BitReader<R>) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+        for count in &self.counts[1..] {
+            code |= input.bits(1)? as i32;
+            let count = *count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(corrupt("invalid Huffman code"))
+    }
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    let mut c = !crc;
+    for byte in bytes {
+        c = CRC_TABLE[((c ^ *byte as u32) & 0xff) as usize] ^ (c >> 8);
+    }
+    !c
+}
+
+fn corrupt(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("gzip: {message}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(0, b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(crc32(0, b"1234"), b"56789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn over_subscribed_code_fails() {
+        assert!(Huffman::new(&[1, 1, 1]).is_err());
+    }
+}
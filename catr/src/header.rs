@@ -0,0 +1,142 @@
+use std::{
+    io::{self, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::Source;
+
+#[derive(Debug, Clone)]
+enum Placeholder {
+    Text(String),
+    Name,
+    Size,
+    Mtime,
+    Lines,
+}
+
+/// A header template with `{name}`, `{size}`, `{mtime}` and `{lines}`
+/// placeholders, `{{` and `}}` standing for literal braces.
+#[derive(Debug, Clone)]
+pub struct HeaderFormat {
+    placeholders: Vec<Placeholder>,
+}
+impl HeaderFormat {
+    pub fn parse(a_format: &str) -> Result<HeaderFormat, String> {
+        let mut placeholders = Vec::new();
+        let mut text = String::new();
+        let mut rest = a_format;
+        while let Some(c) = rest.chars().next() {
+            if let Some(after) = rest.strip_prefix("{{").or(rest.strip_prefix("}}")) {
+                text.push(c);
+                rest = after;
+            } else if c == '{' {
+                let (name, after) = rest[1..]
+                    .split_once('}')
+                    .ok_or(format!("unclosed placeholder in \"{a_format}\""))?;
+                let placeholder = match name {
+                    "name" => Placeholder::Name,
+                    "size" => Placeholder::Size,
+                    "mtime" => Placeholder::Mtime,
+                    "lines" => Placeholder::Lines,
+                    _ => return Err(format!("unknown placeholder \"{{{name}}}\"")),
+                };
+                if !text.is_empty() {
+                    placeholders.push(Placeholder::Text(std::mem::take(&mut text)));
+                }
+                placeholders.push(placeholder);
+                rest = after;
+            } else {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        if !text.is_empty() {
+            placeholders.push(Placeholder::Text(text));
+        }
+        Ok(HeaderFormat { placeholders })
+    }
+
+    /// Whether the input has to be measured before it is printed.
+    pub fn needs_contents(&self) -> bool {
+        self.placeholders
+            .iter()
+            .any(|placeholder| matches!(placeholder, Placeholder::Size | Placeholder::Lines))
+    }
+
+    fn render(&self, filename: &str, source: &mut Source) -> io::Result<String> {
+        let mut header = String::new();
+        for placeholder in &self.placeholders {
+            match placeholder {
+                Placeholder::Text(text) => header.push_str(text),
+                Placeholder::Name => header.push_str(filename),
+                Placeholder::Size => header.push_str(&source.len()?.to_string()),
+                Placeholder::Mtime => {
+                    header.push_str(&source.modified().map_or("-".to_string(), utc))
+                }
+                Placeholder::Lines => header.push_str(&source.count_lines()?.to_string()),
+            }
+        }
+        Ok(header)
+    }
+}
+impl Default for HeaderFormat {
+    fn default() -> Self {
+        HeaderFormat::parse("==> {name} <==").unwrap()
+    }
+}
+
+pub(crate) struct FileHeader<'a> {
+    spaced: bool,
+    filename: &'a str,
+}
+impl<'a> FileHeader<'a> {
+    /// A spaced header is preceded by a blank line, as between headr's
+    /// files.
+    pub fn new(spaced: bool, filename: &'a str) -> Self {
+        FileHeader { spaced, filename }
+    }
+
+    pub fn print(
+        &self,
+        out: &mut dyn Write,
+        header_format: &HeaderFormat,
+        source: &mut Source,
+    ) -> io::Result<()> {
+        writeln!(
+            out,
+            "{}{}",
+            if self.spaced { "\n" } else { "" },
+            header_format.render(self.filename, source)?
+        )
+    }
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ`, from the civil calendar algorithm of
+/// http://howardhinnant.github.io/date_algorithms.html
+fn utc(a_time: SystemTime) -> String {
+    let seconds = match a_time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
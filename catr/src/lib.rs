@@ -1,9 +1,33 @@
+mod copy;
+mod encoding;
+mod glob;
+mod gzip;
+mod header;
+mod number;
+mod reverse;
+mod select;
+mod source;
+mod walk;
+
 use anyhow::Result;
 use clap::Parser;
+use copy::buffered_copy;
+pub use encoding::LineEnding;
+pub use glob::Glob;
+use header::FileHeader;
+pub use header::HeaderFormat;
+pub use number::{NumberAlignment, NumberFormat};
+use reverse::tail_start;
+pub use select::RangeList;
+use select::{ByteRanges, LineSelection};
+use source::{count_lines, Source};
 use std::{
-    fs::File,
-    io::{self, BufRead, BufReader},
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    rc::Rc,
 };
+use walk::Walk;
+
+const OUTPUT_BUFFER_SIZE: usize = 64 * 1024;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
@@ -20,6 +44,177 @@ pub struct Cli {
     /// Number non-blank lines
     #[arg(short('b'), long("number-nonblank"))]
     number_nonblank_lines: bool,
+
+    /// Equivalent to -vET
+    #[arg(short('A'), long)]
+    show_all: bool,
+
+    /// Equivalent to -vE
+    #[arg(short('e'))]
+    show_nonprinting_ends: bool,
+
+    /// Display $ at end of each line
+    #[arg(short('E'), long)]
+    show_ends: bool,
+
+    /// Equivalent to -vT
+    #[arg(short('t'))]
+    show_nonprinting_tabs: bool,
+
+    /// Display TAB characters as ^I
+    #[arg(short('T'), long)]
+    show_tabs: bool,
+
+    /// Use ^ and M- notation, except for LFD and TAB
+    #[arg(short('v'), long)]
+    show_nonprinting: bool,
+
+    /// Suppress repeated empty output lines
+    #[arg(short('s'), long)]
+    squeeze_blank: bool,
+
+    /// Write output as soon as it is read instead of in blocks
+    #[arg(short('u'), long)]
+    unbuffered: bool,
+
+    /// Print only these lines, as START:END[,START:END...] (1-based,
+    /// inclusive, negative counting from the end)
+    #[arg(
+        long,
+        value_name = "RANGES",
+        value_parser = RangeList::parse,
+        allow_hyphen_values = true,
+        conflicts_with = "bytes"
+    )]
+    lines: Option<RangeList>,
+
+    /// Print only these bytes, as START:END[,START:END...] (1-based,
+    /// inclusive, negative counting from the end)
+    #[arg(
+        long,
+        value_name = "RANGES",
+        value_parser = RangeList::parse,
+        allow_hyphen_values = true
+    )]
+    bytes: Option<RangeList>,
+
+    /// Decompress gzip input, detected anyway on regular files and stdin
+    #[arg(short('z'), long)]
+    decompress: bool,
+
+    /// Convert CRLF line endings to LF
+    #[arg(long, conflicts_with = "to_crlf")]
+    to_lf: bool,
+
+    /// Convert LF line endings to CRLF
+    #[arg(long)]
+    to_crlf: bool,
+
+    /// Keep byte order marks and UTF-16 input as they are, instead of
+    /// dropping them and transcoding to UTF-8
+    #[arg(long)]
+    keep_bom: bool,
+
+    /// Concatenate the regular files under directories, in sorted order
+    #[arg(short('r'), long)]
+    recursive: bool,
+
+    /// Only walk files whose name matches GLOB
+    #[arg(
+        long,
+        value_name = "GLOB",
+        value_parser = Glob::parse,
+        requires = "recursive"
+    )]
+    include: Vec<Glob>,
+
+    /// Skip files and directories whose name matches GLOB
+    #[arg(
+        long,
+        value_name = "GLOB",
+        value_parser = Glob::parse,
+        requires = "recursive"
+    )]
+    exclude: Vec<Glob>,
+
+    /// Follow symbolic links while walking directories
+    #[arg(long, requires = "recursive")]
+    follow_symlinks: bool,
+
+    /// Skip files holding a NUL byte in their first 8 KiB
+    #[arg(long)]
+    skip_binary: bool,
+
+    /// Print lines, or records, last to first
+    #[arg(long)]
+    reverse: bool,
+
+    /// End records at STR instead of a newline for --reverse
+    #[arg(
+        long,
+        value_name = "STR",
+        default_value = "\n",
+        allow_hyphen_values = true,
+        value_parser = clap::builder::NonEmptyStringValueParser::new(),
+        requires = "reverse"
+    )]
+    record_separator: String,
+
+    /// Attach the record separator before records instead of after
+    #[arg(long, requires = "reverse")]
+    before: bool,
+
+    /// Print a ==> name <== header before each file
+    #[arg(long)]
+    header: bool,
+
+    /// Print headers as FORMAT, with {name}, {size}, {mtime} and {lines}
+    /// placeholders (implies --header)
+    #[arg(long, value_name = "FORMAT", value_parser = HeaderFormat::parse)]
+    header_format: Option<HeaderFormat>,
+
+    /// Print STR on its own line between files
+    #[arg(long, value_name = "STR", allow_hyphen_values = true)]
+    separator: Option<String>,
+
+    /// Restart line numbers at every file
+    #[arg(long)]
+    number_per_file: bool,
+
+    /// Use N columns for line numbers
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 6,
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    number_width: u16,
+
+    /// Add STR after line numbers
+    #[arg(long, value_name = "STR", default_value = "\t")]
+    number_separator: String,
+
+    /// Insert line numbers according to FORMAT
+    #[arg(long, value_name = "FORMAT", value_enum, default_value_t = NumberAlignment::Rn)]
+    number_format: NumberAlignment,
+
+    /// First line number
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        allow_negative_numbers = true
+    )]
+    starting_line_number: i64,
+
+    /// Line number increment at each line
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        allow_negative_numbers = true
+    )]
+    line_increment: i64,
 }
 impl Cli {
     pub fn files(&self) -> &Vec<String> {
@@ -33,79 +228,474 @@ impl Cli {
     pub fn number_nonblank_lines(&self) -> bool {
         self.number_nonblank_lines
     }
+
+    pub fn number_format(&self) -> NumberFormat {
+        NumberFormat::new(
+            self.number_width as usize,
+            &self.number_separator,
+            self.number_format,
+        )
+    }
+
+    pub fn decompress(&self) -> bool {
+        self.decompress
+    }
+
+    pub fn line_ending(&self) -> Option<LineEnding> {
+        if self.to_lf {
+            Some(LineEnding::Lf)
+        } else if self.to_crlf {
+            Some(LineEnding::Crlf)
+        } else {
+            None
+        }
+    }
+
+    pub fn keep_bom(&self) -> bool {
+        self.keep_bom
+    }
+
+    pub fn recursive(&self) -> bool {
+        self.recursive
+    }
+
+    pub fn include(&self) -> &[Glob] {
+        &self.include
+    }
+
+    pub fn exclude(&self) -> &[Glob] {
+        &self.exclude
+    }
+
+    pub fn follow_symlinks(&self) -> bool {
+        self.follow_symlinks
+    }
+
+    pub fn skip_binary(&self) -> bool {
+        self.skip_binary
+    }
+
+    pub fn reverse(&self) -> bool {
+        self.reverse
+    }
+
+    pub fn record_separator(&self) -> &str {
+        &self.record_separator
+    }
+
+    pub fn before(&self) -> bool {
+        self.before
+    }
+
+    pub fn header_format(&self) -> Option<HeaderFormat> {
+        match &self.header_format {
+            Some(header_format) => Some(header_format.clone()),
+            None if self.header => Some(HeaderFormat::default()),
+            None => None,
+        }
+    }
+
+    pub fn separator(&self) -> Option<&str> {
+        self.separator.as_deref()
+    }
+
+    pub fn number_per_file(&self) -> bool {
+        self.number_per_file
+    }
+
+    /// Whether line numbers carry on from one file to the next.
+    pub fn is_numbered_across_files(&self) -> bool {
+        (self.number_lines() || self.number_nonblank_lines()) && !self.number_per_file()
+    }
+
+    pub fn starting_line_number(&self) -> i64 {
+        self.starting_line_number
+    }
+
+    pub fn line_increment(&self) -> i64 {
+        self.line_increment
+    }
+
+    pub fn lines(&self) -> Option<&RangeList> {
+        self.lines.as_ref()
+    }
+
+    pub fn bytes(&self) -> Option<&RangeList> {
+        self.bytes.as_ref()
+    }
+
+    pub fn is_selected(&self) -> bool {
+        self.lines.is_some() || self.bytes.is_some()
+    }
+
+    pub fn is_formatted(&self) -> bool {
+        self.number_lines()
+            || self.number_nonblank_lines()
+            || self.squeeze_blank()
+            || self.line_ending().is_some()
+            || self.show_ends()
+            || self.show_tabs()
+            || self.show_nonprinting()
+    }
+
+    pub fn unbuffered(&self) -> bool {
+        self.unbuffered
+    }
+
+    pub fn squeeze_blank(&self) -> bool {
+        self.squeeze_blank
+    }
+
+    pub fn show_ends(&self) -> bool {
+        self.show_ends || self.show_all || self.show_nonprinting_ends
+    }
+
+    pub fn show_tabs(&self) -> bool {
+        self.show_tabs || self.show_all || self.show_nonprinting_tabs
+    }
+
+    pub fn show_nonprinting(&self) -> bool {
+        self.show_nonprinting
+            || self.show_all
+            || self.show_nonprinting_ends
+            || self.show_nonprinting_tabs
+    }
 }
 
 pub fn run(cli: &Cli) -> Result<()> {
-    for filename in cli.files() {
-        match open(filename) {
-            Ok(file) => {
-                let mut prev_num = 0;
-                for (line_num, line_result) in file.lines().enumerate() {
-                    let raw_line = line_result?;
-                    let mut line: Box<dyn Printeable> = Box::new(Line::new(&raw_line));
-                    if cli.number_lines() {
-                        line = Box::new(NumberedLine::new(&raw_line, line_num));
-                    } else if cli.number_nonblank_lines() {
-                        let a_line =
-                            NumberedNonblankLine::new(NumberedLine::new(&raw_line, prev_num));
-                        prev_num += a_line.counted();
-                        line = Box::new(a_line);
-                    }
-                    line.print();
-                }
+    let number_format = Rc::new(cli.number_format());
+    let header_format = cli.header_format();
+    let mut position = Position::new(cli.starting_line_number());
+    let mut file_num = 0;
+    let capacity = if cli.unbuffered() {
+        0
+    } else {
+        OUTPUT_BUFFER_SIZE
+    };
+    let mut out = BufWriter::with_capacity(capacity, io::stdout().lock());
+    let walk = Walk::new(
+        cli.files(),
+        cli.recursive(),
+        cli.follow_symlinks(),
+        cli.include(),
+        cli.exclude(),
+    );
+    for path in walk {
+        let filename = path.to_string_lossy();
+        let mut source = match Source::open(&path, cli.decompress()) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("{filename}: {e}");
+                continue;
             }
-            Err(e) => eprintln!("{filename}: {e}"),
+        };
+        let in_file = |e: io::Error| anyhow::Error::new(e).context(filename.to_string());
+        if !cli.keep_bom() {
+            source = source.transcoded().map_err(in_file)?;
         }
+        if cli.skip_binary() && source.looks_binary().map_err(in_file)? {
+            continue;
+        }
+        if let Some(separator) = cli.separator().filter(|_| file_num > 0) {
+            if position.mid_line {
+                writeln!(out)?;
+            }
+            writeln!(out, "{separator}")?;
+            position.mid_line = false;
+        }
+        if let Some(header_format) = &header_format {
+            if header_format.needs_contents() {
+                source = source.seekable().map_err(in_file)?;
+            }
+            let spaced = file_num > 0 && cli.separator().is_none();
+            FileHeader::new(spaced, &filename)
+                .print(&mut out, header_format, &mut source)
+                .map_err(in_file)?;
+            position.mid_line = false;
+        }
+        file_num += 1;
+        if cli.reverse() {
+            source = source
+                .reversed(cli.record_separator().as_bytes(), cli.before())
+                .map_err(in_file)?;
+        }
+        if !cli.is_formatted() && !cli.is_selected() {
+            let last = source
+                .copy_to_stdout(&mut out, cli.separator().is_some())
+                .map_err(in_file)?;
+            if let Some(last) = last {
+                position.mid_line = last != b'\n';
+            }
+            continue;
+        }
+        if cli.number_per_file() {
+            position.line_num = cli.starting_line_number();
+        }
+        print_source(cli, source, &mut out, &number_format, &mut position)
+            .map_err(|e| e.context(filename.to_string()))?;
     }
+    out.flush()?;
     Ok(())
 }
 
-fn open(filename: &str) -> Result<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+/// Whether `error` comes from writing to a closed pipe, which ends the
+/// program quietly.
+pub fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+    })
+}
+
+/// Where the output stands between two input lines, possibly of different
+/// files.
+struct Position {
+    line_num: i64,
+    prev_blank: bool,
+    mid_line: bool,
+}
+impl Position {
+    fn new(line_num: i64) -> Self {
+        Position {
+            line_num,
+            prev_blank: false,
+            mid_line: false,
+        }
     }
 }
 
+fn print_source(
+    cli: &Cli,
+    mut source: Source,
+    out: &mut dyn Write,
+    number_format: &Rc<NumberFormat>,
+    position: &mut Position,
+) -> Result<()> {
+    let mut lines = None;
+    if let Some(ranges) = cli.lines() {
+        let mut total = None;
+        if let Some(tail_len) = ranges.tail_len().filter(|_| source.is_regular_file()) {
+            // Only the tail is read, ranges being resolved within it.
+            let (offset, tail_lines) = tail_start(&mut source, tail_len)?;
+            source.seek_to(0)?;
+            let mut head = BufReader::new((&mut source).take(offset));
+            position.line_num += numbering_of(cli, &mut head)?;
+            source.seek_to(offset)?;
+            total = Some(tail_lines);
+        } else if ranges.is_from_end() {
+            source = source.seekable()?;
+            total = Some(source.count_lines()?);
+        }
+        lines = Some(LineSelection::new(ranges.resolve(total)));
+    }
+    if let Some(ranges) = cli.bytes() {
+        let mut total = None;
+        if ranges.is_from_end() {
+            source = source.seekable()?;
+            total = Some(source.len()?);
+        }
+        let mut selected = ByteRanges::new(source, ranges.resolve(total));
+        if !cli.is_formatted() {
+            if let Some(last) = buffered_copy(&mut selected, out)? {
+                position.mid_line = last != b'\n';
+            }
+            return Ok(());
+        }
+        return print_lines(
+            cli,
+            BufReader::new(selected),
+            out,
+            lines,
+            number_format,
+            position,
+        );
+    }
+    print_lines(
+        cli,
+        BufReader::new(source),
+        out,
+        lines,
+        number_format,
+        position,
+    )
+}
+
+fn print_lines(
+    cli: &Cli,
+    mut file: impl BufRead,
+    out: &mut dyn Write,
+    mut lines: Option<LineSelection>,
+    number_format: &Rc<NumberFormat>,
+    position: &mut Position,
+) -> Result<()> {
+    let mut raw_line = Vec::new();
+    let mut file_line_num = 0;
+    loop {
+        if lines.as_ref().is_some_and(LineSelection::is_done) {
+            // The rest of the file still counts for the numbers of the
+            // next files.
+            if cli.is_numbered_across_files() {
+                position.line_num += numbering_of(cli, &mut file)?;
+            }
+            break;
+        }
+        raw_line.clear();
+        if file.read_until(b'\n', &mut raw_line)? == 0 {
+            break;
+        }
+        if let Some(line_ending) = cli.line_ending() {
+            line_ending.normalize(&mut raw_line);
+        }
+        let mut line: Box<dyn Printeable> = Box::new(Line::new(&raw_line));
+        file_line_num += 1;
+        if lines
+            .as_mut()
+            .is_some_and(|lines| !lines.contains(file_line_num))
+        {
+            // Still counted, so that printed numbers are the original ones.
+            if cli.number_lines() || cli.number_nonblank_lines() && !line.is_blank() {
+                position.line_num += cli.line_increment();
+            }
+            continue;
+        }
+        let continued = std::mem::replace(&mut position.mid_line, !line.line().terminated);
+        if cli.squeeze_blank() && position.prev_blank && line.is_blank() && !continued {
+            continue;
+        }
+        position.prev_blank = line.is_blank() && !continued;
+        if cli.show_nonprinting() {
+            line = Box::new(NonprintingShownLine::new(line));
+        }
+        if cli.show_tabs() {
+            line = Box::new(TabsShownLine::new(line));
+        }
+        if continued {
+            // Rest of a line the previous file left unterminated, numbered
+            // with its start.
+        } else if cli.number_lines() {
+            line = Box::new(NumberedLine::new(line, position.line_num, number_format));
+            position.line_num += cli.line_increment();
+        } else if cli.number_nonblank_lines() {
+            let a_line = NumberedNonblankLine::new(NumberedLine::new(
+                line,
+                position.line_num,
+                number_format,
+            ));
+            position.line_num += a_line.counted() * cli.line_increment();
+            line = Box::new(a_line);
+        }
+        if cli.show_ends() {
+            line = Box::new(EndsShownLine::new(line));
+        }
+        line.print(out)?;
+        if cli.unbuffered() {
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// How much line numbers move over the lines of `file`, read without being
+/// printed.
+fn numbering_of(cli: &Cli, file: &mut impl BufRead) -> Result<i64> {
+    if cli.number_lines() {
+        return Ok(count_lines(file)? as i64 * cli.line_increment());
+    }
+    if !cli.number_nonblank_lines() {
+        return Ok(0);
+    }
+    let mut numbering = 0;
+    let mut raw_line = Vec::new();
+    while file.read_until(b'\n', &mut raw_line)? > 0 {
+        if let Some(line_ending) = cli.line_ending() {
+            line_ending.normalize(&mut raw_line);
+        }
+        if !Line::new(&raw_line).is_blank() {
+            numbering += cli.line_increment();
+        }
+        raw_line.clear();
+    }
+    Ok(numbering)
+}
+
 trait Printeable {
-    fn print(&self);
+    fn as_bytes(&self) -> Vec<u8>;
+
+    fn line(&self) -> &Line;
+
+    fn is_blank(&self) -> bool {
+        self.line().a_line.is_empty()
+    }
+
+    fn print(&self, out: &mut dyn Write) -> Result<()> {
+        out.write_all(&self.as_bytes())?;
+        if self.line().terminated {
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
 }
 
 struct Line {
-    a_line: String,
+    a_line: Vec<u8>,
+    terminated: bool,
 }
 impl Line {
-    fn new(a_line: &str) -> Self {
-        Line {
-            a_line: a_line.to_string(),
+    fn new(a_raw_line: &[u8]) -> Self {
+        match a_raw_line.strip_suffix(b"\n") {
+            Some(a_line) => Line {
+                a_line: a_line.to_vec(),
+                terminated: true,
+            },
+            None => Line {
+                a_line: a_raw_line.to_vec(),
+                terminated: false,
+            },
         }
     }
 }
 impl Printeable for Line {
-    fn print(&self) {
-        println!("{}", self.a_line);
+    fn as_bytes(&self) -> Vec<u8> {
+        self.a_line.to_vec()
+    }
+
+    fn line(&self) -> &Line {
+        self
     }
 }
 
 struct NumberedLine {
-    a_line_number: usize,
-    a_line: String,
+    a_line_number: i64,
+    a_number_format: Rc<NumberFormat>,
+    a_printeable: Box<dyn Printeable>,
 }
 impl NumberedLine {
-    pub fn new(a_line: &str, a_line_number: usize) -> Self {
+    pub fn new(
+        a_printeable: Box<dyn Printeable>,
+        a_line_number: i64,
+        a_number_format: &Rc<NumberFormat>,
+    ) -> Self {
         NumberedLine {
             a_line_number,
-            a_line: a_line.to_string(),
+            a_number_format: Rc::clone(a_number_format),
+            a_printeable,
         }
     }
 
-    pub fn line(&self) -> &str {
-        &self.a_line
+    pub fn unnumbered(&self) -> &dyn Printeable {
+        self.a_printeable.as_ref()
     }
 }
 impl Printeable for NumberedLine {
-    fn print(&self) {
-        println!("{:6}\t{}", self.a_line_number + 1, self.a_line);
+    fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.a_number_format.format(self.a_line_number).into_bytes();
+        bytes.extend(self.a_printeable.as_bytes());
+        bytes
+    }
+
+    fn line(&self) -> &Line {
+        self.a_printeable.line()
     }
 }
 
@@ -117,8 +707,8 @@ impl NumberedNonblankLine {
         Self { a_numbered_line }
     }
 
-    fn counted(&self) -> usize {
-        if self.a_numbered_line.line().is_empty() {
+    fn counted(&self) -> i64 {
+        if self.a_numbered_line.is_blank() {
             0
         } else {
             1
@@ -126,11 +716,103 @@ impl NumberedNonblankLine {
     }
 }
 impl Printeable for NumberedNonblankLine {
-    fn print(&self) {
-        if self.a_numbered_line.line().is_empty() {
-            println!();
+    fn as_bytes(&self) -> Vec<u8> {
+        if self.a_numbered_line.is_blank() {
+            self.a_numbered_line.unnumbered().as_bytes()
         } else {
-            self.a_numbered_line.print();
+            self.a_numbered_line.as_bytes()
+        }
+    }
+
+    fn line(&self) -> &Line {
+        self.a_numbered_line.line()
+    }
+}
+
+struct EndsShownLine {
+    a_printeable: Box<dyn Printeable>,
+}
+impl EndsShownLine {
+    fn new(a_printeable: Box<dyn Printeable>) -> Self {
+        Self { a_printeable }
+    }
+}
+impl Printeable for EndsShownLine {
+    fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.a_printeable.as_bytes();
+        if self.line().terminated {
+            if bytes.last() == Some(&b'\r') {
+                bytes.pop();
+                bytes.extend(b"^M");
+            }
+            bytes.push(b'$');
+        }
+        bytes
+    }
+
+    fn line(&self) -> &Line {
+        self.a_printeable.line()
+    }
+}
+
+struct TabsShownLine {
+    a_printeable: Box<dyn Printeable>,
+}
+impl TabsShownLine {
+    fn new(a_printeable: Box<dyn Printeable>) -> Self {
+        Self { a_printeable }
+    }
+}
+impl Printeable for TabsShownLine {
+    fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for byte in self.a_printeable.as_bytes() {
+            if byte == b'\t' {
+                bytes.extend(b"^I");
+            } else {
+                bytes.push(byte);
+            }
+        }
+        bytes
+    }
+
+    fn line(&self) -> &Line {
+        self.a_printeable.line()
+    }
+}
+
+struct NonprintingShownLine {
+    a_printeable: Box<dyn Printeable>,
+}
+impl NonprintingShownLine {
+    fn new(a_printeable: Box<dyn Printeable>) -> Self {
+        Self { a_printeable }
+    }
+
+    fn push_shown(bytes: &mut Vec<u8>, a_byte: u8) {
+        let mut byte = a_byte;
+        if byte >= 128 {
+            bytes.extend(b"M-");
+            byte -= 128;
+        }
+        match byte {
+            b'\t' if a_byte == b'\t' => bytes.push(b'\t'),
+            0..=31 => bytes.extend([b'^', byte + 64]),
+            127 => bytes.extend(b"^?"),
+            _ => bytes.push(byte),
         }
     }
 }
+impl Printeable for NonprintingShownLine {
+    fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for byte in self.a_printeable.as_bytes() {
+            Self::push_shown(&mut bytes, byte);
+        }
+        bytes
+    }
+
+    fn line(&self) -> &Line {
+        self.a_printeable.line()
+    }
+}
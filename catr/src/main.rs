@@ -3,7 +3,11 @@ use clap::Parser;
 
 fn main() {
     if let Err(e) = catr::run(&Cli::parse()) {
-        eprintln!("{}", e);
+        if catr::is_broken_pipe(&e) {
+            // As if killed by SIGPIPE, which Rust programs ignore.
+            std::process::exit(128 + 13);
+        }
+        eprintln!("{:#}", e);
         std::process::exit(1);
     }
 }
@@ -0,0 +1,37 @@
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NumberAlignment {
+    /// Left justified
+    Ln,
+    /// Right justified
+    Rn,
+    /// Right justified, zero padded
+    Rz,
+}
+
+#[derive(Debug, Clone)]
+pub struct NumberFormat {
+    width: usize,
+    separator: String,
+    alignment: NumberAlignment,
+}
+impl NumberFormat {
+    pub fn new(width: usize, separator: &str, alignment: NumberAlignment) -> Self {
+        NumberFormat {
+            width,
+            separator: separator.to_string(),
+            alignment,
+        }
+    }
+
+    pub fn format(&self, a_line_number: i64) -> String {
+        let width = self.width;
+        let separator = &self.separator;
+        match self.alignment {
+            NumberAlignment::Ln => format!("{a_line_number:<width$}{separator}"),
+            NumberAlignment::Rn => format!("{a_line_number:>width$}{separator}"),
+            NumberAlignment::Rz => format!("{a_line_number:0width$}{separator}"),
+        }
+    }
+}
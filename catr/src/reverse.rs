@@ -0,0 +1,200 @@
+use std::{
+    io::{self, BufRead, Read},
+    mem,
+};
+
+use crate::Source;
+
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// Streams the records of a seekable source last to first, reading it
+/// backwards one block at a time, so that only the record being output is
+/// held in memory.
+pub(crate) struct Reversed {
+    source: Source,
+    separator: Vec<u8>,
+    before: bool,
+    block_size: usize,
+    /// Bytes read so far that are not output yet, found at `window_start`.
+    window: Vec<u8>,
+    window_start: u64,
+    /// Leading bytes of `window` not yet searched for a separator.
+    unsearched: usize,
+    record: Vec<u8>,
+    pos: usize,
+}
+impl Reversed {
+    /// Records end with `separator`, or start with it when `before` is set.
+    pub fn new(mut source: Source, separator: &[u8], before: bool) -> io::Result<Self> {
+        let window_start = source.len()?;
+        Ok(Reversed {
+            source,
+            separator: separator.to_vec(),
+            before,
+            block_size: BLOCK_SIZE,
+            window: Vec::new(),
+            window_start,
+            unsearched: 0,
+            record: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    pub fn get_ref(&self) -> &Source {
+        &self.source
+    }
+
+    /// Moves the last record of the window to `record`, leaving it empty
+    /// once the whole source is output.
+    fn next_record(&mut self) -> io::Result<()> {
+        self.record.clear();
+        self.pos = 0;
+        loop {
+            // A separator ending the window ends the record, it does not
+            // start a new one.
+            let limit = match self.before {
+                true => self.window.len(),
+                false => self.window.len().saturating_sub(1),
+            };
+            let searched = limit.min(self.unsearched + self.separator.len() - 1);
+            if let Some(at) = self.window[..searched]
+                .windows(self.separator.len())
+                .rposition(|window| window == self.separator)
+            {
+                let boundary = match self.before {
+                    true => at,
+                    false => at + self.separator.len(),
+                };
+                self.record = self.window.split_off(boundary);
+                self.unsearched = self.unsearched.min(boundary);
+                return Ok(());
+            }
+            self.unsearched = 0;
+            if self.window_start == 0 {
+                self.record = mem::take(&mut self.window);
+                return Ok(());
+            }
+            let len = self.window_start.min(self.block_size as u64) as usize;
+            self.window_start -= len as u64;
+            let mut block = vec![0; len];
+            self.source.read_exact_at(self.window_start, &mut block)?;
+            block.append(&mut self.window);
+            self.window = block;
+            self.unsearched = len;
+        }
+    }
+}
+/// Where the last `count` lines of a seekable source start, read backwards
+/// one block at a time, and how many lines there are from there, fewer
+/// than `count` when the source is shorter.
+pub(crate) fn tail_start(source: &mut Source, count: u64) -> io::Result<(u64, u64)> {
+    let len = source.len()?;
+    let mut last = [0];
+    if len > 0 {
+        source.read_exact_at(len - 1, &mut last)?;
+    }
+    // A newline ending the source ends its last line, it does not start one.
+    let mut block_end = len - u64::from(last == [b'\n']);
+    let mut found = 0;
+    let mut block = vec![0; BLOCK_SIZE];
+    while block_end > 0 {
+        let block_start = block_end.saturating_sub(BLOCK_SIZE as u64);
+        let block = &mut block[..(block_end - block_start) as usize];
+        source.read_exact_at(block_start, block)?;
+        let newlines = block
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, byte)| **byte == b'\n');
+        for (at, _) in newlines {
+            found += 1;
+            if found == count {
+                return Ok((block_start + at as u64 + 1, count));
+            }
+        }
+        block_end = block_start;
+    }
+    Ok((0, found + u64::from(len > 0)))
+}
+
+impl Read for Reversed {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.fill_buf()?.len());
+        buf[..n].copy_from_slice(&self.record[self.pos..self.pos + n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+impl BufRead for Reversed {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.record.len() {
+            self.next_record()?;
+        }
+        Ok(&self.record[self.pos..])
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.pos += n;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn reverse(input: &str, separator: &str, before: bool) -> String {
+        let mut outputs = Vec::new();
+        for block_size in [1, 2, 3, 5, BLOCK_SIZE] {
+            let source = Source::Buffered(Cursor::new(input.as_bytes().to_vec()));
+            let mut reversed = Reversed::new(source, separator.as_bytes(), before).unwrap();
+            reversed.block_size = block_size;
+            let mut output = String::new();
+            reversed.read_to_string(&mut output).unwrap();
+            outputs.push(output);
+        }
+        assert!(outputs.windows(2).all(|pair| pair[0] == pair[1]));
+        outputs.remove(0)
+    }
+
+    #[test]
+    fn lines_last_to_first() {
+        assert_eq!(reverse("a\nbb\nccc\n", "\n", false), "ccc\nbb\na\n");
+        assert_eq!(reverse("a\n\n\nb\n", "\n", false), "b\n\n\na\n");
+        assert_eq!(reverse("", "\n", false), "");
+    }
+
+    #[test]
+    fn unterminated_last_record() {
+        assert_eq!(reverse("a\nb", "\n", false), "ba\n");
+    }
+
+    #[test]
+    fn separator_before() {
+        assert_eq!(reverse("a\nb\n", "\n", true), "\n\nba");
+        assert_eq!(reverse("\na\nb", "\n", true), "\nb\na");
+    }
+
+    #[test]
+    fn tail_start_of_last_lines() {
+        let tail_start = |input: &str, count| {
+            let mut source = Source::Buffered(Cursor::new(input.as_bytes().to_vec()));
+            super::tail_start(&mut source, count).unwrap()
+        };
+        assert_eq!(tail_start("a\nb\n\nc", 2), (4, 2));
+        assert_eq!(tail_start("a\nb\n\nc\n", 1), (5, 1));
+        assert_eq!(tail_start("a\nb\n", 5), (0, 2));
+        assert_eq!(tail_start("\n", 1), (0, 1));
+        assert_eq!(tail_start("", 1), (0, 0));
+    }
+
+    #[test]
+    fn multibyte_separator_across_blocks() {
+        assert_eq!(
+            reverse("one--two--three--", "--", false),
+            "three--two--one--"
+        );
+        assert_eq!(reverse("one--two--three", "--", true), "--three--twoone");
+        assert_eq!(reverse("a\r\nb\r\n", "\r\n", false), "b\r\na\r\n");
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+    ops::RangeInclusive,
+};
+
+use crate::Source;
+
+/// One `START:END` bound pair, 1-based and inclusive, negative values
+/// counting from the end.
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: Option<i64>,
+    end: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RangeList {
+    spans: Vec<Span>,
+}
+impl RangeList {
+    pub fn parse(a_list: &str) -> Result<RangeList, String> {
+        let spans = a_list
+            .split(',')
+            .map(|a_range| {
+                Self::parse_span(a_range).ok_or(format!("illegal range value \"{a_range}\""))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(RangeList { spans })
+    }
+
+    fn parse_span(a_range: &str) -> Option<Span> {
+        let bound = |value: &str| match value {
+            "" => Some(None),
+            _ => value.parse::<i64>().ok().filter(|n| *n != 0).map(Some),
+        };
+        let span = match a_range.split_once(':') {
+            Some((start, end)) => Span {
+                start: bound(start)?,
+                end: bound(end)?,
+            },
+            None => {
+                let line = bound(a_range)?;
+                Span {
+                    start: Some(line?),
+                    end: line,
+                }
+            }
+        };
+        match (span.start, span.end) {
+            (Some(start), Some(end)) if start.signum() == end.signum() && start > end => None,
+            _ => Some(span),
+        }
+    }
+
+    pub fn is_from_end(&self) -> bool {
+        self.spans
+            .iter()
+            .flat_map(|span| [span.start, span.end])
+            .any(|bound| bound.is_some_and(|n| n < 0))
+    }
+
+    /// How many last lines hold every range, when they all start from the
+    /// end.
+    pub fn tail_len(&self) -> Option<u64> {
+        self.spans
+            .iter()
+            .map(|span| match (span.start, span.end) {
+                (Some(start), None) if start < 0 => Some(start.unsigned_abs()),
+                (Some(start), Some(end)) if start < 0 && end < 0 => Some(start.unsigned_abs()),
+                _ => None,
+            })
+            .try_fold(0, |len, span_len| Some(len.max(span_len?)))
+    }
+
+    /// Sorted, non-overlapping ranges; `total` is only needed when
+    /// `is_from_end`.
+    pub fn resolve(&self, total: Option<u64>) -> Vec<RangeInclusive<u64>> {
+        let position = |bound: i64| match bound {
+            n if n > 0 => n as u64,
+            n => total.unwrap_or(0).saturating_sub(n.unsigned_abs() - 1),
+        };
+        let mut ranges: Vec<RangeInclusive<u64>> = self
+            .spans
+            .iter()
+            .map(|span| {
+                let start = span.start.map_or(1, position).max(1);
+                let end = span.end.map_or(u64::MAX, position);
+                start..=end
+            })
+            .filter(|range| !range.is_empty())
+            .collect();
+        ranges.sort_by_key(|range| *range.start());
+        let mut merged: Vec<RangeInclusive<u64>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if *range.start() <= last.end().saturating_add(1) => {
+                    *last = *last.start()..=*last.end().max(range.end());
+                }
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+}
+
+pub(crate) struct LineSelection {
+    ranges: Vec<RangeInclusive<u64>>,
+    next: usize,
+}
+impl LineSelection {
+    pub fn new(ranges: Vec<RangeInclusive<u64>>) -> Self {
+        LineSelection { ranges, next: 0 }
+    }
+
+    /// Line numbers must be asked in increasing order.
+    pub fn contains(&mut self, a_line_number: u64) -> bool {
+        while self
+            .ranges
+            .get(self.next)
+            .is_some_and(|range| *range.end() < a_line_number)
+        {
+            self.next += 1;
+        }
+        self.ranges
+            .get(self.next)
+            .is_some_and(|range| range.contains(&a_line_number))
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next >= self.ranges.len()
+    }
+}
+
+/// Reads only the selected bytes of a source, skipping the rest.
+pub(crate) struct ByteRanges {
+    source: Source,
+    ranges: VecDeque<RangeInclusive<u64>>,
+    position: u64,
+}
+impl ByteRanges {
+    pub fn new(source: Source, ranges: Vec<RangeInclusive<u64>>) -> Self {
+        ByteRanges {
+            source,
+            ranges: ranges.into(),
+            position: 1,
+        }
+    }
+}
+impl Read for ByteRanges {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(range) = self.ranges.front() {
+            if self.position > *range.end() {
+                self.ranges.pop_front();
+                continue;
+            }
+            if self.position < *range.start() {
+                self.source.skip(range.start() - self.position)?;
+                self.position = *range.start();
+            }
+            let left = usize::try_from(range.end() - self.position + 1).unwrap_or(usize::MAX);
+            let len = buf.len().min(left);
+            let n = self.source.read(&mut buf[..len])?;
+            self.position += n as u64;
+            return Ok(n);
+        }
+        Ok(0)
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, Cursor, Read, Seek, SeekFrom, Stdin, Write},
+    path::Path,
+    time::SystemTime,
+};
+
+use crate::{
+    copy::{buffered_copy, copy_to_stdout},
+    encoding::{Bom, Utf16Decoder},
+    gzip::{GzipDecoder, MAGIC},
+    reverse::Reversed,
+};
+
+const BINARY_CHECK_SIZE: usize = 8 * 1024;
+
+/// An input, kept seekable when it is a regular file.
+pub(crate) enum Source {
+    /// A regular file, whose contents begin at `start`.
+    File {
+        file: File,
+        start: u64,
+    },
+    Pipe(File),
+    Stdin(Stdin),
+    Buffered(Cursor<Vec<u8>>),
+    Gzip(Box<GzipDecoder<Source>>),
+    Utf16(Box<Utf16Decoder<Source>>),
+    Reversed(Box<Reversed>),
+}
+impl Source {
+    /// Gzip input is decompressed when `decompress` is set or, on regular
+    /// files and stdin, when it starts with the gzip magic bytes.
+    pub fn open(a_path: &Path, decompress: bool) -> io::Result<Source> {
+        let mut source = match a_path.to_str() {
+            Some("-") => Source::Stdin(io::stdin()),
+            _ => {
+                let file = File::open(a_path)?;
+                if file.metadata()?.is_file() {
+                    Source::File { file, start: 0 }
+                } else {
+                    Source::Pipe(file)
+                }
+            }
+        };
+        if decompress || source.starts_with(&MAGIC)? {
+            source = Source::Gzip(Box::new(GzipDecoder::new(source)));
+        }
+        Ok(source)
+    }
+
+    /// Drops a byte order mark, transcoding UTF-16 input to UTF-8. As with
+    /// gzip, pipes other than stdin are left alone.
+    pub fn transcoded(mut self) -> io::Result<Source> {
+        let mut bom = None;
+        for a_bom in [Bom::Utf8, Bom::Utf16Le, Bom::Utf16Be] {
+            if self.starts_with(a_bom.bytes())? {
+                bom = Some(a_bom);
+                break;
+            }
+        }
+        let Some(bom) = bom else {
+            return Ok(self);
+        };
+        self.skip(bom.bytes().len() as u64)?;
+        if let Source::File { file, start } = &mut self {
+            *start = file.stream_position()?;
+        }
+        match bom {
+            Bom::Utf8 => Ok(self),
+            Bom::Utf16Le => Ok(Source::Utf16(Box::new(Utf16Decoder::new(self, false)))),
+            Bom::Utf16Be => Ok(Source::Utf16(Box::new(Utf16Decoder::new(self, true)))),
+        }
+    }
+
+    /// Whether the first block holds a NUL byte.
+    pub fn looks_binary(&mut self) -> io::Result<bool> {
+        Ok(self.peek(BINARY_CHECK_SIZE)?.contains(&0))
+    }
+
+    fn starts_with(&mut self, a_prefix: &[u8]) -> io::Result<bool> {
+        Ok(self.peek(a_prefix.len())?.starts_with(a_prefix))
+    }
+
+    /// Up to `len` bytes about to be read, fewer when the source only has
+    /// fewer buffered, none from pipes other than stdin.
+    fn peek(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let head = |buffer: &[u8]| buffer[..len.min(buffer.len())].to_vec();
+        match self {
+            Source::File { file, start } => {
+                let mut peeked = Vec::new();
+                file.take(len as u64).read_to_end(&mut peeked)?;
+                file.seek(SeekFrom::Start(*start))?;
+                Ok(peeked)
+            }
+            Source::Pipe(_) => Ok(Vec::new()),
+            Source::Stdin(stdin) => Ok(head(stdin.lock().fill_buf()?)),
+            Source::Buffered(cursor) => Ok(head(cursor.fill_buf()?)),
+            Source::Gzip(decoder) => Ok(head(decoder.fill_buf()?)),
+            Source::Utf16(decoder) => Ok(head(decoder.fill_buf()?)),
+            Source::Reversed(reversed) => Ok(head(reversed.fill_buf()?)),
+        }
+    }
+
+    /// Reads a stream into memory so that it can be measured and rewound.
+    pub fn seekable(self) -> io::Result<Source> {
+        let mut bytes = Vec::new();
+        match self {
+            Source::File { .. } | Source::Buffered(_) => return Ok(self),
+            Source::Pipe(mut file) => file.read_to_end(&mut bytes)?,
+            Source::Stdin(stdin) => stdin.lock().read_to_end(&mut bytes)?,
+            Source::Gzip(mut decoder) => decoder.read_to_end(&mut bytes)?,
+            Source::Utf16(mut decoder) => decoder.read_to_end(&mut bytes)?,
+            Source::Reversed(mut reversed) => reversed.read_to_end(&mut bytes)?,
+        };
+        Ok(Source::Buffered(Cursor::new(bytes)))
+    }
+
+    /// Records last to first, streams being read into memory first.
+    pub fn reversed(self, a_separator: &[u8], before: bool) -> io::Result<Source> {
+        let source = self.seekable()?;
+        Ok(Source::Reversed(Box::new(Reversed::new(
+            source,
+            a_separator,
+            before,
+        )?)))
+    }
+
+    /// Size in bytes of a seekable source.
+    pub fn len(&mut self) -> io::Result<u64> {
+        match self {
+            Source::File { file, start } => Ok(file.metadata()?.len() - *start),
+            Source::Buffered(cursor) => Ok(cursor.get_ref().len() as u64),
+            _ => Err(io::ErrorKind::Unsupported.into()),
+        }
+    }
+
+    pub fn modified(&self) -> Option<SystemTime> {
+        match self {
+            Source::File { file, .. } | Source::Pipe(file) => file.metadata().ok()?.modified().ok(),
+            Source::Gzip(decoder) => decoder.get_ref().modified(),
+            Source::Utf16(decoder) => decoder.get_ref().modified(),
+            Source::Reversed(reversed) => reversed.get_ref().modified(),
+            Source::Stdin(_) | Source::Buffered(_) => None,
+        }
+    }
+
+    /// Number of lines of a seekable source, counting an unterminated last
+    /// one, leaving it rewound.
+    pub fn count_lines(&mut self) -> io::Result<u64> {
+        let lines = count_lines(self)?;
+        self.rewind()?;
+        Ok(lines)
+    }
+
+    /// Fills `buf` from `offset` of a seekable source.
+    pub fn read_exact_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        match self {
+            Source::File { file, start } => file.seek(SeekFrom::Start(*start + offset))?,
+            Source::Buffered(cursor) => cursor.seek(SeekFrom::Start(offset))?,
+            _ => return Err(io::ErrorKind::Unsupported.into()),
+        };
+        self.read_exact(buf)
+    }
+
+    pub fn skip(&mut self, n: u64) -> io::Result<()> {
+        match self {
+            Source::File { file, .. } => file.seek(SeekFrom::Current(n as i64)).map(|_| ()),
+            Source::Buffered(cursor) => cursor.seek(SeekFrom::Current(n as i64)).map(|_| ()),
+            source => io::copy(&mut source.take(n), &mut io::sink()).map(|_| ()),
+        }
+    }
+
+    /// Copies what is left to `out`, which must write to stdout, returning
+    /// the last byte copied. Pipes and stdin only tell it with `keep_last`,
+    /// which copies them through a buffer rather than in the kernel.
+    pub fn copy_to_stdout(
+        &mut self,
+        out: &mut dyn Write,
+        keep_last: bool,
+    ) -> io::Result<Option<u8>> {
+        match self {
+            Source::File { file, .. } => copy_to_stdout(file, out)?,
+            Source::Pipe(file) if !keep_last => {
+                copy_to_stdout(file, out)?;
+                return Ok(None);
+            }
+            Source::Stdin(stdin) if !keep_last => {
+                // Bytes peeked while looking for a gzip magic or a byte
+                // order mark are already out of the file descriptor.
+                let mut stdin = stdin.lock();
+                let peeked = stdin.fill_buf()?.to_vec();
+                out.write_all(&peeked)?;
+                stdin.consume(peeked.len());
+                copy_to_stdout(&mut stdin, out)?;
+                return Ok(None);
+            }
+            source => return buffered_copy(source, out),
+        }
+        let len = self.len()?;
+        if len == 0 {
+            return Ok(None);
+        }
+        let mut last = [0];
+        self.read_exact_at(len - 1, &mut last)?;
+        Ok(Some(last[0]))
+    }
+
+    pub fn is_regular_file(&self) -> bool {
+        matches!(self, Source::File { .. })
+    }
+
+    /// Moves a seekable source to `offset` from its start.
+    pub fn seek_to(&mut self, offset: u64) -> io::Result<()> {
+        match self {
+            Source::File { file, start } => file.seek(SeekFrom::Start(*start + offset)).map(|_| ()),
+            Source::Buffered(cursor) => cursor.seek(SeekFrom::Start(offset)).map(|_| ()),
+            _ => Err(io::ErrorKind::Unsupported.into()),
+        }
+    }
+
+    fn rewind(&mut self) -> io::Result<()> {
+        self.seek_to(0)
+    }
+}
+
+/// Number of lines left in `input`, counting an unterminated last one.
+pub(crate) fn count_lines(input: &mut impl Read) -> io::Result<u64> {
+    let mut buffer = vec![0; 64 * 1024];
+    let mut lines = 0;
+    let mut last = b'\n';
+    loop {
+        let n = input.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        lines += buffer[..n].iter().filter(|byte| **byte == b'\n').count() as u64;
+        last = buffer[n - 1];
+    }
+    Ok(lines + u64::from(last != b'\n'))
+}
+
+impl Read for Source {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Source::File { file, .. } | Source::Pipe(file) => file.read(buf),
+            Source::Stdin(stdin) => stdin.read(buf),
+            Source::Buffered(cursor) => cursor.read(buf),
+            Source::Gzip(decoder) => decoder.read(buf),
+            Source::Utf16(decoder) => decoder.read(buf),
+            Source::Reversed(reversed) => reversed.read(buf),
+        }
+    }
+}
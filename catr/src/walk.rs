@@ -0,0 +1,129 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::Glob;
+
+struct Entry {
+    path: PathBuf,
+    operand: bool,
+    ancestors: Rc<Vec<PathBuf>>,
+}
+
+/// The files to concatenate: operands as given, and with `recursive` the
+/// regular files under directory operands, in sorted order.
+pub(crate) struct Walk<'a> {
+    recursive: bool,
+    follow_symlinks: bool,
+    include: &'a [Glob],
+    exclude: &'a [Glob],
+    pending: Vec<Entry>,
+}
+impl<'a> Walk<'a> {
+    pub fn new(
+        operands: &[String],
+        recursive: bool,
+        follow_symlinks: bool,
+        include: &'a [Glob],
+        exclude: &'a [Glob],
+    ) -> Self {
+        let pending = operands
+            .iter()
+            .rev()
+            .map(|operand| Entry {
+                path: PathBuf::from(operand),
+                operand: true,
+                ancestors: Rc::new(Vec::new()),
+            })
+            .collect();
+        Walk {
+            recursive,
+            follow_symlinks,
+            include,
+            exclude,
+            pending,
+        }
+    }
+
+    /// Globs are matched against file names, `--exclude` pruning
+    /// directories too.
+    fn is_selected(&self, a_path: &Path) -> bool {
+        let name = a_path.file_name().unwrap_or_default().to_string_lossy();
+        (self.include.is_empty() || self.include.iter().any(|glob| glob.matches(&name)))
+            && !self.is_excluded(a_path)
+    }
+
+    fn is_excluded(&self, a_path: &Path) -> bool {
+        let name = a_path.file_name().unwrap_or_default().to_string_lossy();
+        self.exclude.iter().any(|glob| glob.matches(&name))
+    }
+
+    fn push_children(&mut self, a_directory: Entry) {
+        let identity = match fs::canonicalize(&a_directory.path) {
+            Ok(identity) => identity,
+            Err(e) => return eprintln!("{}: {e}", a_directory.path.display()),
+        };
+        if a_directory.ancestors.contains(&identity) {
+            return eprintln!(
+                "{}: directory loop, already visited as {}",
+                a_directory.path.display(),
+                identity.display()
+            );
+        }
+        let mut children = match fs::read_dir(&a_directory.path)
+            .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        {
+            Ok(children) => children,
+            Err(e) => return eprintln!("{}: {e}", a_directory.path.display()),
+        };
+        children.sort_by_key(|child| child.file_name());
+        let mut ancestors = a_directory.ancestors.to_vec();
+        ancestors.push(identity);
+        let ancestors = Rc::new(ancestors);
+        self.pending
+            .extend(children.iter().rev().map(|child| Entry {
+                path: child.path(),
+                operand: false,
+                ancestors: Rc::clone(&ancestors),
+            }));
+    }
+}
+impl Iterator for Walk<'_> {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<PathBuf> {
+        while let Some(entry) = self.pending.pop() {
+            if entry.operand && entry.path == Path::new("-") {
+                return Some(entry.path);
+            }
+            let metadata = if entry.operand || self.follow_symlinks {
+                fs::metadata(&entry.path)
+            } else {
+                fs::symlink_metadata(&entry.path)
+            };
+            match metadata {
+                // Operands that cannot be read are reported when opened.
+                Err(_) if entry.operand => {}
+                Err(e) => {
+                    eprintln!("{}: {e}", entry.path.display());
+                    continue;
+                }
+                Ok(metadata) if metadata.is_dir() => {
+                    if !self.recursive {
+                        eprintln!("{}: Is a directory", entry.path.display());
+                    } else if entry.operand || !self.is_excluded(&entry.path) {
+                        self.push_children(entry);
+                    }
+                    continue;
+                }
+                Ok(_) if entry.operand => {}
+                Ok(metadata) if metadata.is_file() && self.is_selected(&entry.path) => {}
+                Ok(_) => continue,
+            }
+            return Some(entry.path);
+        }
+        None
+    }
+}
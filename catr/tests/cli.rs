@@ -10,6 +10,26 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const SPIDERS: &str = "tests/inputs/spiders.txt";
 const BUSTLE: &str = "tests/inputs/the-bustle.txt";
+const NONPRINTING: &str = "tests/inputs/nonprinting.txt";
+const BLANKS: &str = "tests/inputs/blanks.txt";
+const BLANKS2: &str = "tests/inputs/blanks2.txt";
+const CRLF: &str = "tests/inputs/crlf.txt";
+const NO_NEWLINE: &str = "tests/inputs/no-newline.txt";
+const BINARY: &str = "tests/inputs/binary.bin";
+const UNTERMINATED: &str = "tests/inputs/unterminated.txt";
+const TWENTY: &str = "tests/inputs/twenty.txt";
+const FOX_GZ: &str = "tests/inputs/fox.txt.gz";
+const BUSTLE_GZ: &str = "tests/inputs/the-bustle.txt.gz";
+const MULTI_GZ: &str = "tests/inputs/multi.txt.gz";
+const STORED_GZ: &str = "tests/inputs/stored.bin.gz";
+const REPEATED_GZ: &str = "tests/inputs/repeated.txt.gz";
+const TRUNCATED_GZ: &str = "tests/inputs/truncated.txt.gz";
+const BAD_CRC_GZ: &str = "tests/inputs/bad-crc.txt.gz";
+const UTF16LE: &str = "tests/inputs/utf16le.txt";
+const UTF16BE: &str = "tests/inputs/utf16be.txt";
+const UTF8_BOM: &str = "tests/inputs/utf8-bom.txt";
+const UNPAIRED: &str = "tests/inputs/unpaired.txt";
+const TREE: &str = "tests/inputs/tree";
 
 // --------------------------------------------------
 #[test]
@@ -63,6 +83,16 @@ fn run(args: &[&str], expected_file: &str) -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+fn run_bytes(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read(expected_file)?;
+    let output = Command::cargo_bin(PRG)?.args(args).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run_stdin(
     input_file: &str,
@@ -201,3 +231,802 @@ fn all_n() -> Result<()> {
 fn all_b() -> Result<()> {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn all_n_per_file() -> Result<()> {
+    run(
+        &[FOX, SPIDERS, BUSTLE, "-n", "--number-per-file"],
+        "tests/expected/all.n.per-file.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn all_b_per_file() -> Result<()> {
+    run(
+        &[FOX, SPIDERS, BUSTLE, "-b", "--number-per-file"],
+        "tests/expected/all.b.per-file.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn unterminated_blanks_n() -> Result<()> {
+    run(
+        &["-n", UNTERMINATED, BLANKS],
+        "tests/expected/unterminated.blanks.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_nonprinting() -> Result<()> {
+    run(
+        &["--show-nonprinting", NONPRINTING],
+        "tests/expected/nonprinting.txt.v.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_ends() -> Result<()> {
+    run(
+        &["--show-ends", NONPRINTING],
+        "tests/expected/nonprinting.txt.E.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_tabs() -> Result<()> {
+    run(
+        &["--show-tabs", NONPRINTING],
+        "tests/expected/nonprinting.txt.T.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_all() -> Result<()> {
+    run(
+        &["--show-all", NONPRINTING],
+        "tests/expected/nonprinting.txt.A.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_e() -> Result<()> {
+    run(&["-e", NONPRINTING], "tests/expected/nonprinting.txt.e.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_t() -> Result<()> {
+    run(&["-t", NONPRINTING], "tests/expected/nonprinting.txt.t.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_n_show_all() -> Result<()> {
+    run(
+        &["-nA", NONPRINTING],
+        "tests/expected/nonprinting.txt.nA.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_b_show_ends() -> Result<()> {
+    run(
+        &["-bE", NONPRINTING],
+        "tests/expected/nonprinting.txt.bE.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_s() -> Result<()> {
+    run(&["-s", BLANKS], "tests/expected/blanks.txt.s.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_s_n() -> Result<()> {
+    run(
+        &["--squeeze-blank", "-n", BLANKS],
+        "tests/expected/blanks.txt.sn.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_s_b() -> Result<()> {
+    run(&["-s", "-b", BLANKS], "tests/expected/blanks.txt.sb.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_all_s() -> Result<()> {
+    run(&["-s", BLANKS, BLANKS2], "tests/expected/blanks.all.s.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf() -> Result<()> {
+    run_bytes(&[CRLF], "tests/expected/crlf.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_n() -> Result<()> {
+    run_bytes(&["-n", CRLF], "tests/expected/crlf.txt.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_show_ends() -> Result<()> {
+    run_bytes(&["-E", CRLF], "tests/expected/crlf.txt.E.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn no_newline() -> Result<()> {
+    run_bytes(&[NO_NEWLINE], "tests/expected/no-newline.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn no_newline_n() -> Result<()> {
+    run_bytes(&["-n", NO_NEWLINE], "tests/expected/no-newline.txt.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn no_newline_b() -> Result<()> {
+    run_bytes(&["-b", NO_NEWLINE], "tests/expected/no-newline.txt.b.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn no_newline_show_ends() -> Result<()> {
+    run_bytes(&["-E", NO_NEWLINE], "tests/expected/no-newline.txt.E.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn no_newline_then_crlf() -> Result<()> {
+    run_bytes(&[NO_NEWLINE, CRLF], "tests/expected/no-newline.crlf.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary() -> Result<()> {
+    run_bytes(&[BINARY], "tests/expected/binary.bin.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_n() -> Result<()> {
+    run_bytes(&["-n", BINARY], "tests/expected/binary.bin.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_show_all() -> Result<()> {
+    run_bytes(&["-A", BINARY], "tests/expected/binary.bin.A.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_stdin_crlf() -> Result<()> {
+    let expected = fs::read("tests/expected/binary.stdin.crlf.out")?;
+    let output = Command::cargo_bin(PRG)?
+        .write_stdin("from stdin\n")
+        .args([BINARY, "-", CRLF])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_n_zero_padded() -> Result<()> {
+    run(
+        &[
+            "-n",
+            "--number-width",
+            "3",
+            "--number-separator",
+            ": ",
+            "--number-format",
+            "rz",
+            "--starting-line-number",
+            "10",
+            "--line-increment",
+            "5",
+            BUSTLE,
+        ],
+        "tests/expected/the-bustle.txt.n.rz.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_b_zero_padded() -> Result<()> {
+    run(
+        &[
+            "-b",
+            "--number-format=rz",
+            "--number-width=2",
+            "--starting-line-number=0",
+            "--line-increment=2",
+            "--number-separator= ",
+            BUSTLE,
+        ],
+        "tests/expected/the-bustle.txt.b.rz.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn spiders_n_left_justified() -> Result<()> {
+    run(
+        &[
+            "-n",
+            "--number-width=4",
+            "--number-separator=|",
+            "--number-format=ln",
+            SPIDERS,
+        ],
+        "tests/expected/spiders.txt.n.ln.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_n_negative_start() -> Result<()> {
+    run(
+        &["-n", "--starting-line-number", "-2", FOX],
+        "tests/expected/fox.txt.n.negative.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_zero_number_width() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-n", "--number-width", "0", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--number-width"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn twenty_lines() -> Result<()> {
+    run(
+        &["--lines", "3:5,8", TWENTY],
+        "tests/expected/twenty.txt.lines.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn twenty_lines_from_end() -> Result<()> {
+    run(
+        &["--lines", "-3:", TWENTY],
+        "tests/expected/twenty.txt.lines.tail.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn lines_n_across_files() -> Result<()> {
+    run(
+        &["-n", "--lines", "1:2", TWENTY, FOX],
+        "tests/expected/twenty.fox.lines.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn lines_from_end_n_across_files() -> Result<()> {
+    run(
+        &["-n", "--lines=-2:", TWENTY, FOX],
+        "tests/expected/twenty.fox.lines.tail.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn lines_b_across_files() -> Result<()> {
+    run(
+        &["-b", "--lines", "1:3", BLANKS, FOX],
+        "tests/expected/blanks.fox.lines.b.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn twenty_lines_stdin_n() -> Result<()> {
+    run_stdin(
+        TWENTY,
+        &["-n", "--lines=-5:-3,2"],
+        "tests/expected/twenty.txt.lines.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_lines_b() -> Result<()> {
+    run(
+        &["-b", "--lines", "5:7", BUSTLE],
+        "tests/expected/the-bustle.txt.lines.b.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn twenty_bytes() -> Result<()> {
+    run(
+        &["--bytes", "4:9", TWENTY],
+        "tests/expected/twenty.txt.bytes.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn twenty_bytes_stdin_from_end() -> Result<()> {
+    run_stdin(
+        TWENTY,
+        &["--bytes", "-6:"],
+        "tests/expected/twenty.txt.bytes.tail.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_range() -> Result<()> {
+    for range in ["5:3", "0", "a:b", "1:2,"] {
+        Command::cargo_bin(PRG)?
+            .args(["--lines", range, TWENTY])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("illegal range value"));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn all_header() -> Result<()> {
+    run(&["--header", FOX, SPIDERS], "tests/expected/all.header.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn all_header_format_separator() -> Result<()> {
+    run(
+        &[
+            "-n",
+            "--header-format",
+            "# {name}: {size} bytes, {lines} lines",
+            "--separator",
+            "----",
+            FOX,
+            NO_NEWLINE,
+        ],
+        "tests/expected/all.header-format.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn separator_after_no_newline() -> Result<()> {
+    run(
+        &["--separator=---", NO_NEWLINE, FOX],
+        "tests/expected/no-newline.fox.separator.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn separator_after_no_newline_stdin() -> Result<()> {
+    run_stdin(
+        NO_NEWLINE,
+        &["--separator=---", "-", FOX],
+        "tests/expected/no-newline.fox.separator.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn header_format_stdin() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .write_stdin("a\nb\nc")
+        .args(["--header-format", "{{{name}}} {size} {lines} {mtime}"])
+        .assert()
+        .success()
+        .stdout("{-} 5 3 -\na\nb\nc");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn header_format_mtime() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--header-format", "{mtime}", FOX])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z\n",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_header_format() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--header-format", "{path}", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown placeholder"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_gz() -> Result<()> {
+    run(&[FOX_GZ], "tests/expected/fox.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_gz_n() -> Result<()> {
+    run(&["-n", BUSTLE_GZ], "tests/expected/the-bustle.txt.gz.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_gz_stdin() -> Result<()> {
+    let input = fs::read(BUSTLE_GZ)?;
+    let expected = fs::read(BUSTLE)?;
+    let output = Command::cargo_bin(PRG)?
+        .write_stdin(input)
+        .arg("-z")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn multi_member_gz() -> Result<()> {
+    run(&[MULTI_GZ], "tests/expected/multi.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn stored_gz() -> Result<()> {
+    run_bytes(
+        &["--decompress", STORED_GZ],
+        "tests/expected/binary.bin.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn repeated_gz() -> Result<()> {
+    let expected = [fs::read_to_string(SPIDERS)?, fs::read_to_string(BUSTLE)?]
+        .concat()
+        .repeat(1000);
+    let output = Command::cargo_bin(PRG)?.arg(REPEATED_GZ).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_truncated_gz() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .arg(TRUNCATED_GZ)
+        .assert()
+        .failure()
+        .stderr(format!(
+            "{TRUNCATED_GZ}: gzip: unexpected end of input, file truncated\n"
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_crc_gz() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .arg(BAD_CRC_GZ)
+        .assert()
+        .failure()
+        .stderr(format!("{BAD_CRC_GZ}: gzip: CRC32 mismatch\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_decompress_plain() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-z", FOX])
+        .assert()
+        .failure()
+        .stderr(format!("{FOX}: gzip: not in gzip format\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_to_lf() -> Result<()> {
+    run_bytes(&["--to-lf", CRLF], "tests/expected/crlf.txt.lf.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_to_lf_n() -> Result<()> {
+    run_bytes(&["--to-lf", "-n", CRLF], "tests/expected/crlf.txt.lf.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn spiders_to_crlf() -> Result<()> {
+    run_bytes(
+        &["--to-crlf", SPIDERS],
+        "tests/expected/spiders.txt.crlf.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn utf16le() -> Result<()> {
+    run_bytes(&[UTF16LE], "tests/expected/utf16.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn utf16be_to_lf() -> Result<()> {
+    run_bytes(&["--to-lf", UTF16BE], "tests/expected/utf16.txt.lf.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn utf8_bom() -> Result<()> {
+    run_bytes(&[UTF8_BOM], "tests/expected/utf16.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn utf16le_keep_bom() -> Result<()> {
+    run_bytes(&["--keep-bom", UTF16LE], UTF16LE)
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unpaired_surrogate() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .arg(UNPAIRED)
+        .assert()
+        .failure()
+        .stderr(format!(
+            "{UNPAIRED}: invalid UTF-16: unpaired surrogate 0xd800 at byte 4\n"
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_to_lf_and_to_crlf() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--to-lf", "--to-crlf", CRLF])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn directory_without_recursive() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([TREE, FOX])
+        .assert()
+        .success()
+        .stdout("The quick brown fox jumps over the lazy dog.\n")
+        .stderr(format!("{TREE}: Is a directory\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn tree_recursive() -> Result<()> {
+    run(
+        &["-r", "--header", TREE],
+        "tests/expected/tree.r.header.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tree_include() -> Result<()> {
+    run(
+        &["-r", "--header", "--include", "*.txt", TREE],
+        "tests/expected/tree.r.include.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tree_exclude_skip_binary() -> Result<()> {
+    run(
+        &[
+            "-r",
+            "--header",
+            "--skip-binary",
+            "--exclude",
+            "logs",
+            "--exclude",
+            "*.log",
+            TREE,
+        ],
+        "tests/expected/tree.r.exclude.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_include_without_recursive() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--include", "*.txt", TREE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--recursive"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unclosed_glob() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-r", "--exclude", "[a-", TREE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unclosed [ in pattern \"[a-\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[cfg(unix)]
+#[test]
+fn follow_symlinks_loop() -> Result<()> {
+    let root = std::env::temp_dir().join(format!("catr-{}", gen_bad_file()));
+    fs::create_dir_all(root.join("dir"))?;
+    fs::write(root.join("dir/file.txt"), "in the loop\n")?;
+    std::os::unix::fs::symlink("..", root.join("dir/up"))?;
+    let root_arg = root.to_string_lossy().to_string();
+
+    let output = Command::cargo_bin(PRG)?.args(["-r", &root_arg]).output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "in the loop\n");
+
+    let output = Command::cargo_bin(PRG)?
+        .args(["-r", "--follow-symlinks", &root_arg])
+        .output()?;
+    fs::remove_dir_all(&root)?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "in the loop\n");
+    assert_eq!(
+        String::from_utf8(output.stderr)?,
+        format!(
+            "{root_arg}/dir/up: directory loop, already visited as {}\n",
+            root.canonicalize().unwrap_or(root.clone()).display()
+        )
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_reverse() -> Result<()> {
+    run(
+        &["--reverse", BUSTLE],
+        "tests/expected/the-bustle.txt.reverse.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_reverse_n() -> Result<()> {
+    run(
+        &["--reverse", "-n", BUSTLE],
+        "tests/expected/the-bustle.txt.reverse.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_reverse_stdin() -> Result<()> {
+    run_stdin(
+        BUSTLE,
+        &["--reverse"],
+        "tests/expected/the-bustle.txt.reverse.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn reverse_unterminated() -> Result<()> {
+    run(
+        &["--reverse", NO_NEWLINE, SPIDERS],
+        "tests/expected/no-newline.spiders.reverse.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn spiders_reverse_before() -> Result<()> {
+    run(
+        &["--reverse", "--before", "--record-separator", " ", SPIDERS],
+        "tests/expected/spiders.txt.reverse.before.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_before_without_reverse() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--before", SPIDERS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--reverse"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_unbuffered_n() -> Result<()> {
+    run(&["-u", "-n", BUSTLE], "tests/expected/the-bustle.txt.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn quiet_on_broken_pipe() -> Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::Stdio;
+
+    for args in [&["-"][..], &["-n", "-"]] {
+        let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_catr"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().unwrap();
+        let writer = std::thread::spawn(move || {
+            let line = "a line that keeps coming\n".repeat(1024);
+            while stdin.write_all(line.as_bytes()).is_ok() {}
+        });
+        let mut first = String::new();
+        BufReader::new(child.stdout.take().unwrap()).read_line(&mut first)?;
+        assert!(first.ends_with("a line that keeps coming\n"));
+
+        let output = child.wait_with_output()?;
+        writer.join().unwrap();
+        assert_eq!(output.status.code(), Some(141));
+        assert_eq!(String::from_utf8(output.stderr)?, "");
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::ops::RangeInclusive;
+
+const KEY_SEPARATOR: char = '\u{1f}';
+
+#[derive(Debug, Clone)]
+pub struct FieldList {
+    ranges: Vec<RangeInclusive<usize>>,
+}
+impl FieldList {
+    pub fn parse(a_list: &str) -> Result<FieldList, String> {
+        let ranges = a_list
+            .split(',')
+            .map(|a_range| {
+                Self::parse_range(a_range).ok_or(format!("illegal field value \"{a_range}\""))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(FieldList { ranges })
+    }
+
+    fn parse_range(a_range: &str) -> Option<RangeInclusive<usize>> {
+        let field = |value: &str| value.parse::<usize>().ok().filter(|field| *field > 0);
+        match a_range.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (field(start)?, field(end)?);
+                (start <= end).then_some(start - 1..=end - 1)
+            }
+            None => field(a_range).map(|field| field - 1..=field - 1),
+        }
+    }
+}
+
+pub(crate) struct FieldKey {
+    fields: FieldList,
+    delimiter: char,
+}
+impl FieldKey {
+    pub fn new(fields: FieldList, delimiter: char) -> Self {
+        FieldKey { fields, delimiter }
+    }
+
+    pub fn key(&self, a_record: &str) -> String {
+        let values = self.values(a_record);
+        let mut key = String::new();
+        for (position, index) in self.fields.ranges.iter().cloned().flatten().enumerate() {
+            if position > 0 {
+                key.push(KEY_SEPARATOR);
+            }
+            key.push_str(values.get(index).map_or("", String::as_str));
+        }
+        key
+    }
+
+    // Splits a record into its field values, honoring RFC 4180 quotes so
+    // that a quoted delimiter stays inside its field.
+    fn values(&self, a_record: &str) -> Vec<String> {
+        let mut values = Vec::new();
+        let mut value = String::new();
+        let mut chars = a_record.chars().peekable();
+        let mut in_quotes = false;
+        let mut at_field_start = true;
+        while let Some(c) = chars.next() {
+            if in_quotes {
+                match c {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        value.push('"');
+                    }
+                    '"' => in_quotes = false,
+                    c => value.push(c),
+                }
+            } else if c == '"' && at_field_start {
+                in_quotes = true;
+                at_field_start = false;
+            } else if c == self.delimiter {
+                values.push(std::mem::take(&mut value));
+                at_field_start = true;
+            } else {
+                value.push(c);
+                at_field_start = false;
+            }
+        }
+        values.push(value);
+        values
+    }
+}
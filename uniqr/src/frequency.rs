@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::{with_count, GroupRecord, OutputFormat, WriteBuffer};
+
+pub(crate) struct FrequencyTable {
+    entries: HashMap<String, Frequency>,
+    total: usize,
+}
+impl FrequencyTable {
+    pub fn new() -> Self {
+        FrequencyTable {
+            entries: HashMap::new(),
+            total: 0,
+        }
+    }
+
+    pub fn add(&mut self, a_line: &str, a_key: &str, a_line_number: usize) {
+        self.total += 1;
+        match self.entries.get_mut(a_key) {
+            Some(frequency) => {
+                frequency.count += 1;
+                frequency.last_line_number = a_line_number;
+            }
+            None => {
+                self.entries.insert(
+                    a_key.to_string(),
+                    Frequency {
+                        a_line: a_line.to_string(),
+                        count: 1,
+                        first_line_number: a_line_number,
+                        last_line_number: a_line_number,
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn print(
+        self,
+        top: Option<usize>,
+        min_count: usize,
+        show_percent: bool,
+        output_format: OutputFormat,
+        delimiter: char,
+        write_buffer: &mut WriteBuffer,
+    ) -> Result<()> {
+        let mut frequencies: Vec<Frequency> = self
+            .entries
+            .into_values()
+            .filter(|frequency| frequency.count >= min_count)
+            .collect();
+        frequencies.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.a_line.cmp(&b.a_line)));
+        for frequency in frequencies.iter().take(top.unwrap_or(usize::MAX)) {
+            match output_format {
+                OutputFormat::Text => {
+                    let a_line = if show_percent {
+                        let percent = 100.0 * frequency.count as f64 / self.total as f64;
+                        format!("{percent:6.2}% {}", frequency.a_line)
+                    } else {
+                        frequency.a_line.to_string()
+                    };
+                    write_buffer.write(&with_count(frequency.count, &a_line))?;
+                    write_buffer.write_char(delimiter)?;
+                }
+                OutputFormat::Jsonl => write_buffer.write(&frequency.as_record().as_jsonl())?,
+                OutputFormat::Csv => write_buffer.write(&frequency.as_record().as_csv())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Frequency {
+    a_line: String,
+    count: usize,
+    first_line_number: usize,
+    last_line_number: usize,
+}
+impl Frequency {
+    fn as_record(&self) -> GroupRecord<'_> {
+        GroupRecord {
+            a_line: &self.a_line,
+            count: self.count,
+            first_line_number: self.first_line_number,
+            last_line_number: self.last_line_number,
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+
+use clap::ValueEnum;
+
+const SHINGLE_SIZE: usize = 2;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum FuzzyMethod {
+    Levenshtein,
+    Jaccard,
+}
+
+pub(crate) enum KeyMatcher {
+    Exact,
+    Fuzzy(FuzzyMethod, f64),
+}
+impl KeyMatcher {
+    pub fn matches(&self, a_key: &str, another_key: &str) -> bool {
+        match self {
+            KeyMatcher::Exact => a_key == another_key,
+            KeyMatcher::Fuzzy(_, _) if a_key == another_key => true,
+            KeyMatcher::Fuzzy(FuzzyMethod::Levenshtein, threshold) => {
+                Self::levenshtein_similarity(a_key, another_key) >= *threshold
+            }
+            KeyMatcher::Fuzzy(FuzzyMethod::Jaccard, threshold) => {
+                Self::jaccard_similarity(a_key, another_key) >= *threshold
+            }
+        }
+    }
+
+    fn levenshtein_similarity(a_key: &str, another_key: &str) -> f64 {
+        let a_chars: Vec<char> = a_key.chars().collect();
+        let other_chars: Vec<char> = another_key.chars().collect();
+        let longest = a_chars.len().max(other_chars.len());
+        if longest == 0 {
+            return 1.0;
+        }
+        let mut previous: Vec<usize> = (0..=other_chars.len()).collect();
+        let mut current = vec![0; other_chars.len() + 1];
+        for (i, a_char) in a_chars.iter().enumerate() {
+            current[0] = i + 1;
+            for (j, other_char) in other_chars.iter().enumerate() {
+                let substitution = previous[j] + usize::from(a_char != other_char);
+                current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            }
+            std::mem::swap(&mut previous, &mut current);
+        }
+        1.0 - previous[other_chars.len()] as f64 / longest as f64
+    }
+
+    fn jaccard_similarity(a_key: &str, another_key: &str) -> f64 {
+        let a_shingles = Self::shingles(a_key);
+        let other_shingles = Self::shingles(another_key);
+        let union = a_shingles.union(&other_shingles).count();
+        if union == 0 {
+            return 1.0;
+        }
+        a_shingles.intersection(&other_shingles).count() as f64 / union as f64
+    }
+
+    fn shingles(a_key: &str) -> HashSet<Vec<&str>> {
+        let tokens: Vec<&str> = a_key.split_whitespace().collect();
+        if tokens.len() < SHINGLE_SIZE {
+            return HashSet::from([tokens]);
+        }
+        tokens
+            .windows(SHINGLE_SIZE)
+            .map(|shingle| shingle.to_vec())
+            .collect()
+    }
+}
+
+pub(crate) struct TokenMask {
+    digits: bool,
+    hex: bool,
+}
+impl TokenMask {
+    pub fn new(digits: bool, hex: bool) -> Self {
+        TokenMask { digits, hex }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.digits || self.hex
+    }
+
+    pub fn mask(&self, a_key: &str) -> String {
+        let mut masked = String::with_capacity(a_key.len());
+        let mut rest = a_key;
+        while let Some(c) = rest.chars().next() {
+            let token_len = if c.is_ascii_alphanumeric() {
+                rest.find(|c: char| !c.is_ascii_alphanumeric())
+                    .unwrap_or(rest.len())
+            } else {
+                c.len_utf8()
+            };
+            let (token, after) = rest.split_at(token_len);
+            if self.hex && Self::is_hex(token) {
+                masked.push('#');
+            } else if self.digits {
+                Self::push_masking_digits(&mut masked, token);
+            } else {
+                masked.push_str(token);
+            }
+            rest = after;
+        }
+        masked
+    }
+
+    fn is_hex(a_token: &str) -> bool {
+        let digits = a_token
+            .strip_prefix("0x")
+            .or_else(|| a_token.strip_prefix("0X"));
+        match digits {
+            Some(digits) => !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit()),
+            None => {
+                a_token.chars().all(|c| c.is_ascii_hexdigit())
+                    && a_token.chars().any(|c| c.is_ascii_digit())
+            }
+        }
+    }
+
+    fn push_masking_digits(masked: &mut String, a_token: &str) {
+        let mut in_digits = false;
+        for c in a_token.chars() {
+            if c.is_ascii_digit() {
+                if !in_digits {
+                    masked.push('#');
+                }
+                in_digits = true;
+            } else {
+                masked.push(c);
+                in_digits = false;
+            }
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::DefaultHasher, BinaryHeap, HashSet},
+    fs::File,
+    hash::{Hash, Hasher},
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+};
+
+use anyhow::Result;
+
+use crate::WriteBuffer;
+
+const BYTES_PER_DIGEST: usize = 64;
+/// Runs merged at once, keeping open files well under the usual limit.
+const MAX_MERGED_RUNS: usize = 16;
+
+pub(crate) struct FirstOccurrences {
+    seen: HashSet<u128>,
+    chunk: Vec<(u128, u64)>,
+    max_digests: usize,
+    index: u64,
+    runs: Runs<(u128, u64)>,
+    first_candidate: u64,
+    candidates: Option<BufWriter<File>>,
+}
+impl FirstOccurrences {
+    pub fn new(max_memory: usize) -> Self {
+        FirstOccurrences {
+            seen: HashSet::new(),
+            chunk: Vec::new(),
+            max_digests: (max_memory / BYTES_PER_DIGEST).max(1),
+            index: 0,
+            runs: Runs::new(),
+            first_candidate: 0,
+            candidates: None,
+        }
+    }
+
+    pub fn add(&mut self, a_line: &str, a_key: &str, write_buffer: &mut WriteBuffer) -> Result<()> {
+        let index = self.index;
+        self.index += 1;
+        let digest = Self::digest(a_key);
+        if !self.seen.insert(digest) {
+            return Ok(());
+        }
+        self.chunk.push((digest, index));
+        match self.candidates.as_mut() {
+            Some(candidates) => {
+                index.write_to(candidates)?;
+                (a_line.len() as u64).write_to(candidates)?;
+                candidates.write_all(a_line.as_bytes())?;
+            }
+            None => write_buffer.write(a_line)?,
+        }
+        if self.seen.len() >= self.max_digests {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self, write_buffer: &mut WriteBuffer) -> Result<()> {
+        let Some(candidates) = self.candidates.take() else {
+            return Ok(());
+        };
+        if !self.chunk.is_empty() {
+            self.write_run()?;
+        }
+        let mut keepers = self.keepers()?.into_sorted()?;
+        let mut candidates = BufReader::new(rewound(candidates.into_inner()?)?);
+        while let Some(keeper) = keepers.next()? {
+            loop {
+                let index = u64::read_from(&mut candidates)?;
+                let mut a_line = vec![0; u64::read_from(&mut candidates)? as usize];
+                candidates.read_exact(&mut a_line)?;
+                if index == keeper {
+                    write_buffer.write(&String::from_utf8(a_line)?)?;
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> Result<()> {
+        self.write_run()?;
+        if self.candidates.is_none() {
+            self.candidates = Some(BufWriter::new(tempfile::tempfile()?));
+            self.first_candidate = self.index;
+        }
+        Ok(())
+    }
+
+    fn write_run(&mut self) -> Result<()> {
+        self.runs.push(sorted_run(&mut self.chunk)?)?;
+        self.seen.clear();
+        Ok(())
+    }
+
+    fn keepers(&mut self) -> Result<Runs<u64>> {
+        let mut digests = std::mem::replace(&mut self.runs, Runs::new()).into_sorted()?;
+        let mut keepers = Runs::new();
+        let mut pending = Vec::new();
+        let mut previous_digest = None;
+        while let Some((digest, index)) = digests.next()? {
+            // Runs are ordered by digest and then by index, so the first
+            // record of every digest is its first occurrence in the input.
+            if previous_digest == Some(digest) {
+                continue;
+            }
+            previous_digest = Some(digest);
+            if index >= self.first_candidate {
+                pending.push(index);
+                if pending.len() >= self.max_digests {
+                    keepers.push(sorted_run(&mut pending)?)?;
+                }
+            }
+        }
+        if !pending.is_empty() {
+            keepers.push(sorted_run(&mut pending)?)?;
+        }
+        Ok(keepers)
+    }
+
+    fn digest(a_key: &str) -> u128 {
+        let mut high = DefaultHasher::new();
+        (0u8, a_key).hash(&mut high);
+        let mut low = DefaultHasher::new();
+        (1u8, a_key).hash(&mut low);
+        (high.finish() as u128) << 64 | low.finish() as u128
+    }
+}
+
+fn sorted_run<T: Record>(records: &mut Vec<T>) -> Result<File> {
+    records.sort_unstable();
+    let mut run = BufWriter::new(tempfile::tempfile()?);
+    for record in records.drain(..) {
+        record.write_to(&mut run)?;
+    }
+    rewound(run.into_inner()?)
+}
+
+fn rewound(mut file: File) -> Result<File> {
+    file.seek(SeekFrom::Start(0))?;
+    Ok(file)
+}
+
+trait Record: Ord + Sized {
+    fn write_to(&self, writer: &mut impl Write) -> io::Result<()>;
+    fn read_from(reader: &mut impl Read) -> io::Result<Self>;
+}
+impl Record for u64 {
+    fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+
+    fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut bytes = [0; 8];
+        reader.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+impl Record for (u128, u64) {
+    fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(&self.0.to_le_bytes())?;
+        self.1.write_to(writer)
+    }
+
+    fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut bytes = [0; 16];
+        reader.read_exact(&mut bytes)?;
+        Ok((u128::from_le_bytes(bytes), u64::read_from(reader)?))
+    }
+}
+
+/// Sorted runs on disk, merged `MAX_MERGED_RUNS` at a time as they pile up,
+/// so that few files are open at once.
+struct Runs<T: Record> {
+    /// Runs by how many merges they went through.
+    levels: Vec<Vec<File>>,
+    records: PhantomData<T>,
+}
+impl<T: Record> Runs<T> {
+    fn new() -> Self {
+        Runs {
+            levels: Vec::new(),
+            records: PhantomData,
+        }
+    }
+
+    fn push(&mut self, mut run: File) -> Result<()> {
+        for level in 0.. {
+            if self.levels.len() == level {
+                self.levels.push(Vec::new());
+            }
+            self.levels[level].push(run);
+            if self.levels[level].len() < MAX_MERGED_RUNS {
+                break;
+            }
+            run = merged::<T>(self.levels[level].drain(..).collect())?;
+        }
+        Ok(())
+    }
+
+    fn into_sorted(self) -> Result<SortedRuns<T>> {
+        let mut runs: Vec<File> = self.levels.into_iter().flatten().collect();
+        while runs.len() > MAX_MERGED_RUNS {
+            let mut batches = runs.into_iter().peekable();
+            runs = Vec::new();
+            while batches.peek().is_some() {
+                runs.push(merged::<T>(
+                    batches.by_ref().take(MAX_MERGED_RUNS).collect(),
+                )?);
+            }
+        }
+        SortedRuns::new(runs)
+    }
+}
+
+fn merged<T: Record>(runs: Vec<File>) -> Result<File> {
+    let mut sorted_runs = SortedRuns::<T>::new(runs)?;
+    let mut run = BufWriter::new(tempfile::tempfile()?);
+    while let Some(record) = sorted_runs.next()? {
+        record.write_to(&mut run)?;
+    }
+    rewound(run.into_inner()?)
+}
+
+struct SortedRuns<T: Record> {
+    readers: Vec<BufReader<File>>,
+    heap: BinaryHeap<Reverse<(T, usize)>>,
+}
+impl<T: Record> SortedRuns<T> {
+    fn new(runs: Vec<File>) -> Result<Self> {
+        let mut sorted_runs = SortedRuns {
+            readers: runs.into_iter().map(BufReader::new).collect(),
+            heap: BinaryHeap::new(),
+        };
+        for run in 0..sorted_runs.readers.len() {
+            sorted_runs.advance(run)?;
+        }
+        Ok(sorted_runs)
+    }
+
+    fn next(&mut self) -> Result<Option<T>> {
+        let Some(Reverse((record, run))) = self.heap.pop() else {
+            return Ok(None);
+        };
+        self.advance(run)?;
+        Ok(Some(record))
+    }
+
+    fn advance(&mut self, run: usize) -> Result<()> {
+        match T::read_from(&mut self.readers[run]) {
+            Ok(record) => self.heap.push(Reverse((record, run))),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {}
+            Err(e) => return Err(e.into()),
+        }
+        Ok(())
+    }
+}
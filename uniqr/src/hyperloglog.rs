@@ -0,0 +1,207 @@
+use std::{collections::HashSet, fs, io::Read};
+
+use anyhow::{anyhow, bail, Result};
+
+const MAGIC: &[u8; 4] = b"UHLL";
+const VERSION: u8 = 1;
+
+pub(crate) struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+impl HyperLogLog {
+    pub fn new(precision: u8) -> Self {
+        HyperLogLog {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    pub fn load(a_filename: &str) -> Result<Self> {
+        let bytes = fs::read(a_filename).map_err(|e| anyhow!("{a_filename}: {e}"))?;
+        Self::from_bytes(&bytes).map_err(|e| anyhow!("{a_filename}: {e}"))
+    }
+
+    pub fn save(&self, a_filename: &str) -> Result<()> {
+        fs::write(a_filename, self.to_bytes()).map_err(|e| anyhow!("{a_filename}: {e}"))
+    }
+
+    pub fn add(&mut self, a_key: &str) {
+        let hash = Self::hash(a_key.as_bytes());
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rank = ((hash << self.precision) | (1 << (self.precision - 1))).leading_zeros() + 1;
+        self.registers[index] = self.registers[index].max(rank as u8);
+    }
+
+    pub fn merge(&mut self, other: &HyperLogLog) -> Result<()> {
+        if self.precision != other.precision {
+            bail!(
+                "cannot merge a sketch of precision {} into one of precision {}",
+                other.precision,
+                self.precision
+            );
+        }
+        for (register, other_register) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*other_register);
+        }
+        Ok(())
+    }
+
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|register| 2f64.powi(-(*register as i32)))
+            .sum();
+        let raw_estimate = alpha * m * m / sum;
+        let zeros = self
+            .registers
+            .iter()
+            .filter(|register| **register == 0)
+            .count();
+        if raw_estimate <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw_estimate
+        }
+    }
+
+    pub fn standard_error(&self) -> f64 {
+        1.04 / (self.registers.len() as f64).sqrt()
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 2 + self.registers.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.precision);
+        bytes.extend_from_slice(&self.registers);
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = bytes;
+        let mut header = [0; 6];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| anyhow!("not a uniqr sketch"))?;
+        if &header[..4] != MAGIC {
+            bail!("not a uniqr sketch");
+        }
+        if header[4] != VERSION {
+            bail!("unsupported sketch version {}", header[4]);
+        }
+        let precision = header[5];
+        if !(4..=18).contains(&precision) {
+            bail!("invalid sketch precision {precision}");
+        }
+        let mut registers = Vec::new();
+        reader.read_to_end(&mut registers)?;
+        if registers.len() != 1 << precision {
+            bail!("truncated sketch");
+        }
+        Ok(HyperLogLog {
+            precision,
+            registers,
+        })
+    }
+
+    // FNV-1a followed by the SplitMix64 finalizer, so that sketches saved by
+    // one build can be merged by another.
+    fn hash(bytes: &[u8]) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        hash ^ (hash >> 31)
+    }
+}
+
+pub(crate) enum DistinctCounter {
+    Exact(HashSet<String>),
+    Approximate(HyperLogLog),
+}
+impl DistinctCounter {
+    pub fn add(&mut self, a_key: &str) {
+        match self {
+            DistinctCounter::Exact(keys) => {
+                if !keys.contains(a_key) {
+                    keys.insert(a_key.to_string());
+                }
+            }
+            DistinctCounter::Approximate(sketch) => sketch.add(a_key),
+        }
+    }
+
+    pub fn as_string(&self) -> String {
+        match self {
+            DistinctCounter::Exact(keys) => format!("{}\n", keys.len()),
+            DistinctCounter::Approximate(sketch) => {
+                let estimate = sketch.estimate();
+                format!(
+                    "{:.0}\t± {:.0} ({:.2}% standard error)\n",
+                    estimate,
+                    estimate * sketch.standard_error(),
+                    100.0 * sketch.standard_error()
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn a_sketch_of(lines: std::ops::Range<usize>) -> HyperLogLog {
+        let mut sketch = HyperLogLog::new(12);
+        for line in lines {
+            sketch.add(&line.to_string());
+        }
+        sketch
+    }
+
+    #[test]
+    fn estimate_within_three_standard_errors() {
+        let sketch = a_sketch_of(0..100_000);
+
+        let error = (sketch.estimate() - 100_000.0).abs() / 100_000.0;
+        assert!(error < 3.0 * sketch.standard_error(), "error {error}");
+    }
+
+    #[test]
+    fn merge_is_union() -> Result<()> {
+        let mut sketch = a_sketch_of(0..6_000);
+        sketch.merge(&a_sketch_of(4_000..10_000))?;
+
+        assert_eq!(sketch.registers, a_sketch_of(0..10_000).registers);
+        Ok(())
+    }
+
+    #[test]
+    fn bytes_round_trip() -> Result<()> {
+        let sketch = a_sketch_of(0..1_000);
+
+        let loaded = HyperLogLog::from_bytes(&sketch.to_bytes())?;
+        assert_eq!(loaded.precision, sketch.precision);
+        assert_eq!(loaded.registers, sketch.registers);
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_bytes_fail() {
+        let bytes = a_sketch_of(0..10).to_bytes();
+
+        assert!(HyperLogLog::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -1,10 +1,32 @@
 use std::{
-    fs::File,
-    io::{self, BufRead, BufReader, Write},
+    borrow::Cow,
+    collections::{HashSet, VecDeque},
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    str,
 };
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, ValueEnum};
+
+mod fields;
+mod frequency;
+mod fuzzy;
+mod global;
+mod hyperloglog;
+mod output;
+mod timestamp;
+
+use fields::FieldKey;
+pub use fields::FieldList;
+use frequency::FrequencyTable;
+pub use fuzzy::FuzzyMethod;
+use fuzzy::{KeyMatcher, TokenMask};
+use global::FirstOccurrences;
+use hyperloglog::{DistinctCounter, HyperLogLog};
+use output::GroupRecord;
+pub use output::OutputFormat;
+use timestamp::{TimeFormat, TimeWindow};
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
@@ -21,10 +43,225 @@ pub struct Cli {
     /// Show counts
     #[arg(short, long)]
     count: bool,
+
+    /// Only print one copy of each repeated group
+    #[arg(short('d'), long)]
+    repeated: bool,
+
+    /// Only print unique lines
+    #[arg(short('u'), long)]
+    unique: bool,
+
+    /// Print all duplicate lines, optionally delimiting groups
+    #[arg(
+        short('D'),
+        long,
+        value_name = "METHOD",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "none",
+        conflicts_with_all(["count", "unique"])
+    )]
+    all_repeated: Option<AllRepeatedMethod>,
+
+    /// Show all lines, delimiting groups with an empty line
+    #[arg(
+        long,
+        value_name = "METHOD",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "separate",
+        conflicts_with_all(["count", "repeated", "unique", "all_repeated"])
+    )]
+    group: Option<GroupMethod>,
+
+    /// Avoid comparing the first N fields
+    #[arg(short('f'), long, value_name = "N", default_value = "0")]
+    skip_fields: usize,
+
+    /// Avoid comparing the first N characters
+    #[arg(short('s'), long, value_name = "N", default_value = "0")]
+    skip_chars: usize,
+
+    /// Compare no more than N characters in lines
+    #[arg(short('w'), long, value_name = "N")]
+    check_chars: Option<usize>,
+
+    /// Ignore differences in case when comparing
+    #[arg(short('i'), long)]
+    ignore_case: bool,
+
+    /// Drop every line already seen earlier, not only adjacent ones
+    #[arg(
+        long,
+        conflicts_with_all(["count", "repeated", "unique", "all_repeated", "group"])
+    )]
+    global: bool,
+
+    /// Memory for --global digests before spilling to disk (K, M, G suffixes)
+    #[arg(long, value_name = "SIZE", default_value = "1G", value_parser = parse_size, requires = "global")]
+    max_memory: usize,
+
+    /// Count every distinct line and print them by descending count
+    #[arg(
+        long,
+        conflicts_with_all(["repeated", "unique", "all_repeated", "group", "global"])
+    )]
+    freq: bool,
+
+    /// Only print the N most frequent lines
+    #[arg(long, value_name = "N", requires = "freq")]
+    top: Option<usize>,
+
+    /// Only print lines seen at least N times
+    #[arg(long, value_name = "N", default_value = "1", requires = "freq")]
+    min_count: usize,
+
+    /// Show the percentage of the total next to each count
+    #[arg(long, requires = "freq")]
+    percent: bool,
+
+    /// Print the first line of a group and a "last message repeated" summary
+    #[arg(
+        long,
+        conflicts_with_all(["count", "repeated", "unique", "all_repeated", "group", "global", "freq"])
+    )]
+    squash_repeats: bool,
+
+    /// Start a new group when repeats are more than SECS apart
+    #[arg(long, value_name = "SECS", requires = "squash_repeats")]
+    window: Option<u64>,
+
+    /// Format of the leading timestamp read by --window
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "%b %e %H:%M:%S",
+        requires = "window"
+    )]
+    time_format: String,
+
+    /// Print an estimate of the number of distinct lines
+    #[arg(
+        long,
+        conflicts_with_all(["count", "repeated", "unique", "all_repeated", "group", "global", "freq", "squash_repeats"])
+    )]
+    count_distinct: bool,
+
+    /// HyperLogLog precision; the sketch uses 2^P registers
+    #[arg(
+        long,
+        value_name = "P",
+        default_value = "14",
+        value_parser = clap::value_parser!(u8).range(4..=18),
+        requires = "count_distinct"
+    )]
+    precision: u8,
+
+    /// Count distinct lines exactly with a hash set
+    #[arg(long, requires = "count_distinct", conflicts_with_all(["save_sketch", "merge_sketch"]))]
+    exact: bool,
+
+    /// Save the sketch to FILE
+    #[arg(long, value_name = "FILE", requires = "count_distinct")]
+    save_sketch: Option<String>,
+
+    /// Merge the sketch saved in FILE before estimating
+    #[arg(long, value_name = "FILE", requires = "count_distinct")]
+    merge_sketch: Vec<String>,
+
+    /// Group adjacent lines that are similar rather than identical
+    #[arg(
+        long,
+        value_name = "METHOD",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "levenshtein",
+        conflicts_with_all(["global", "freq", "count_distinct"])
+    )]
+    fuzzy: Option<FuzzyMethod>,
+
+    /// Minimum similarity, from 0 to 1, for --fuzzy to join a group
+    #[arg(long, value_name = "SIMILARITY", default_value = "0.8", value_parser = parse_threshold, requires = "fuzzy")]
+    threshold: f64,
+
+    /// Replace runs of digits with "#" before comparing
+    #[arg(long)]
+    mask_digits: bool,
+
+    /// Replace hexadecimal tokens with "#" before comparing
+    #[arg(long)]
+    mask_hex: bool,
+
+    /// Read several input files one after another as a single stream
+    #[arg(
+        long,
+        value_name = "FILE",
+        num_args = 1..,
+        conflicts_with_all(["in_file", "out_file"])
+    )]
+    files: Vec<String>,
+
+    /// Line delimiter is NUL, not newline
+    #[arg(short('z'), long)]
+    zero_terminated: bool,
+
+    /// Print each group as a text line, a JSON Lines record or a CSV row
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_enum,
+        default_value = "text",
+        conflicts_with_all(["all_repeated", "group", "global", "squash_repeats", "count_distinct"])
+    )]
+    output_format: OutputFormat,
+
+    /// Compare only these fields, e.g. "2,4-5"
+    #[arg(
+        short('k'),
+        long,
+        value_name = "FIELDS",
+        value_parser = FieldList::parse,
+        conflicts_with_all(["skip_fields", "skip_chars", "check_chars"])
+    )]
+    key: Option<FieldList>,
+
+    /// Field delimiter for --key
+    #[arg(
+        short('t'),
+        long("delimiter"),
+        value_name = "DELIM",
+        default_value = ",",
+        requires = "key"
+    )]
+    field_delimiter: char,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum AllRepeatedMethod {
+    None,
+    Prepend,
+    Separate,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GroupMethod {
+    Separate,
+    Prepend,
+    Append,
+    Both,
 }
+
 impl Cli {
-    pub fn in_file(&self) -> &str {
-        &self.in_file
+    pub fn in_files(&self) -> Vec<&str> {
+        if self.files.is_empty() {
+            vec![&self.in_file]
+        } else {
+            self.files.iter().map(String::as_str).collect()
+        }
     }
     pub fn out_file(&self) -> Option<&str> {
         self.out_file.as_deref()
@@ -32,35 +269,632 @@ impl Cli {
     pub fn count(&self) -> bool {
         self.count
     }
+    pub fn repeated(&self) -> bool {
+        self.repeated
+    }
+    pub fn unique(&self) -> bool {
+        self.unique
+    }
+    pub fn all_repeated(&self) -> Option<AllRepeatedMethod> {
+        self.all_repeated
+    }
+    pub fn group(&self) -> Option<GroupMethod> {
+        self.group
+    }
+    pub fn skip_fields(&self) -> usize {
+        self.skip_fields
+    }
+    pub fn skip_chars(&self) -> usize {
+        self.skip_chars
+    }
+    pub fn check_chars(&self) -> Option<usize> {
+        self.check_chars
+    }
+    pub fn ignore_case(&self) -> bool {
+        self.ignore_case
+    }
+    pub fn global(&self) -> bool {
+        self.global
+    }
+    pub fn max_memory(&self) -> usize {
+        self.max_memory
+    }
+    pub fn freq(&self) -> bool {
+        self.freq
+    }
+    pub fn top(&self) -> Option<usize> {
+        self.top
+    }
+    pub fn min_count(&self) -> usize {
+        self.min_count
+    }
+    pub fn percent(&self) -> bool {
+        self.percent
+    }
+    pub fn squash_repeats(&self) -> bool {
+        self.squash_repeats
+    }
+    pub fn window(&self) -> Option<u64> {
+        self.window
+    }
+    pub fn time_format(&self) -> &str {
+        &self.time_format
+    }
+    pub fn count_distinct(&self) -> bool {
+        self.count_distinct
+    }
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+    pub fn exact(&self) -> bool {
+        self.exact
+    }
+    pub fn save_sketch(&self) -> Option<&str> {
+        self.save_sketch.as_deref()
+    }
+    pub fn merge_sketch(&self) -> &Vec<String> {
+        &self.merge_sketch
+    }
+    pub fn fuzzy(&self) -> Option<FuzzyMethod> {
+        self.fuzzy
+    }
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+    pub fn mask_digits(&self) -> bool {
+        self.mask_digits
+    }
+    pub fn mask_hex(&self) -> bool {
+        self.mask_hex
+    }
+    pub fn key(&self) -> Option<&FieldList> {
+        self.key.as_ref()
+    }
+    pub fn field_delimiter(&self) -> char {
+        self.field_delimiter
+    }
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format
+    }
+    pub fn delimiter(&self) -> u8 {
+        if self.zero_terminated {
+            b'\0'
+        } else {
+            b'\n'
+        }
+    }
 }
 
 pub fn run(cli: Cli) -> Result<()> {
-    match ReadBuffer::of(cli.in_file()) {
-        Ok(mut read_buffer) => {
-            let mut line = String::new();
-            loop {
-                let bytes = read_buffer.read_line(&mut line)?;
-                if bytes.is_zero() {
-                    break;
-                }
-                let result = if cli.count() {
-                    format!("{:4} {}", 1, line)
-                } else {
-                    line.to_string()
-                };
-
-                if let Some(out_filename) = cli.out_file() {
-                    let mut out_file = File::create(out_filename)?;
-                    out_file.write_all(result.as_bytes())?;
-                } else {
-                    print!("{}", result);
-                }
+    let mut read_buffer = ReadBuffer::of(&cli.in_files(), cli.delimiter())?;
+    let mut write_buffer = WriteBuffer::of(&cli.in_files(), cli.out_file())?;
+    let line_key = LineKey::of(&cli);
+    if cli.output_format() == OutputFormat::Csv {
+        write_buffer.write(GroupRecord::csv_header())?;
+    }
+    if cli.global() {
+        print_first_occurrences(&cli, &mut read_buffer, &line_key, &mut write_buffer)?;
+    } else if cli.freq() {
+        print_frequencies(&cli, &mut read_buffer, &line_key, &mut write_buffer)?;
+    } else if cli.count_distinct() {
+        print_distinct_count(&cli, &mut read_buffer, &line_key, &mut write_buffer)?;
+    } else {
+        print_adjacent_groups(&cli, &mut read_buffer, &line_key, &mut write_buffer)?;
+    }
+    write_buffer.flush()
+}
+
+fn print_adjacent_groups(
+    cli: &Cli,
+    read_buffer: &mut ReadBuffer,
+    line_key: &LineKey,
+    write_buffer: &mut WriteBuffer,
+) -> Result<()> {
+    let mut printer = printer_of(cli);
+    let key_matcher = match cli.fuzzy() {
+        Some(method) => KeyMatcher::Fuzzy(method, cli.threshold()),
+        None => KeyMatcher::Exact,
+    };
+    let time_window = match cli.window() {
+        Some(seconds) => Some(TimeWindow::new(
+            seconds,
+            TimeFormat::new(cli.time_format())?,
+        )),
+        None => None,
+    };
+    let mut line = String::new();
+    let mut line_number = 0;
+    let mut group = LineGroup::new();
+    let mut previous_time = None;
+    loop {
+        let bytes = read_buffer.read_record(&mut line)?;
+        if bytes.is_zero() {
+            break;
+        }
+        line_number += 1;
+        let (time, message) = match &time_window {
+            Some(time_window) => time_window.split(&line),
+            None => (None, line.as_str()),
+        };
+        let key = line_key.key(message);
+        let in_window = time_window
+            .as_ref()
+            .is_none_or(|time_window| time_window.contains(previous_time, time));
+        previous_time = time.or(previous_time);
+        if group.accepts(&key, &key_matcher) && in_window {
+            group.add(line_number);
+            printer.repeated(&group, &line, write_buffer)?;
+        } else {
+            if !group.is_empty() {
+                printer.finished(&group, write_buffer)?;
             }
-            line.clear();
+            group.restart(&line, &key, line_number);
+            printer.started(&group, write_buffer)?;
+        }
+        line.clear();
+    }
+    if !group.is_empty() {
+        printer.finished(&group, write_buffer)?;
+    }
+    printer.ended(write_buffer)
+}
+
+fn print_first_occurrences(
+    cli: &Cli,
+    read_buffer: &mut ReadBuffer,
+    line_key: &LineKey,
+    write_buffer: &mut WriteBuffer,
+) -> Result<()> {
+    let mut first_occurrences = FirstOccurrences::new(cli.max_memory());
+    let mut line = String::new();
+    loop {
+        let bytes = read_buffer.read_record(&mut line)?;
+        if bytes.is_zero() {
+            break;
+        }
+        first_occurrences.add(&line, &line_key.key(&line), write_buffer)?;
+        line.clear();
+    }
+    first_occurrences.finish(write_buffer)
+}
+
+fn print_frequencies(
+    cli: &Cli,
+    read_buffer: &mut ReadBuffer,
+    line_key: &LineKey,
+    write_buffer: &mut WriteBuffer,
+) -> Result<()> {
+    let mut frequency_table = FrequencyTable::new();
+    let mut line = String::new();
+    let mut line_number = 0;
+    loop {
+        let bytes = read_buffer.read_record(&mut line)?;
+        if bytes.is_zero() {
+            break;
+        }
+        line_number += 1;
+        frequency_table.add(line_key.record(&line), &line_key.key(&line), line_number);
+        line.clear();
+    }
+    frequency_table.print(
+        cli.top(),
+        cli.min_count(),
+        cli.percent(),
+        cli.output_format(),
+        char::from(cli.delimiter()),
+        write_buffer,
+    )
+}
+
+fn print_distinct_count(
+    cli: &Cli,
+    read_buffer: &mut ReadBuffer,
+    line_key: &LineKey,
+    write_buffer: &mut WriteBuffer,
+) -> Result<()> {
+    let mut distinct_counter = if cli.exact() {
+        DistinctCounter::Exact(HashSet::new())
+    } else {
+        let mut sketch = HyperLogLog::new(cli.precision());
+        for filename in cli.merge_sketch() {
+            sketch.merge(&HyperLogLog::load(filename)?)?;
+        }
+        DistinctCounter::Approximate(sketch)
+    };
+    let mut line = String::new();
+    loop {
+        let bytes = read_buffer.read_record(&mut line)?;
+        if bytes.is_zero() {
+            break;
+        }
+        distinct_counter.add(&line_key.key(&line));
+        line.clear();
+    }
+    if let (Some(filename), DistinctCounter::Approximate(sketch)) =
+        (cli.save_sketch(), &distinct_counter)
+    {
+        sketch.save(filename)?;
+    }
+    write_buffer.write(&distinct_counter.as_string())
+}
+
+fn with_count(count: usize, a_line: &str) -> String {
+    format!("{:4} {}", count, a_line)
+}
+
+fn parse_threshold(a_threshold: &str) -> Result<f64, String> {
+    a_threshold
+        .parse::<f64>()
+        .ok()
+        .filter(|threshold| (0.0..=1.0).contains(threshold))
+        .ok_or_else(|| format!("invalid threshold \"{a_threshold}\", expected 0 to 1"))
+}
+
+fn parse_size(a_size: &str) -> Result<usize, String> {
+    let (digits, multiplier) = match a_size.char_indices().last() {
+        Some((index, 'K' | 'k')) => (&a_size[..index], 1 << 10),
+        Some((index, 'M' | 'm')) => (&a_size[..index], 1 << 20),
+        Some((index, 'G' | 'g')) => (&a_size[..index], 1 << 30),
+        _ => (a_size, 1),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|size| size.checked_mul(multiplier))
+        .filter(|size| *size > 0)
+        .ok_or_else(|| format!("invalid size \"{a_size}\""))
+}
+
+fn printer_of(cli: &Cli) -> Box<dyn GroupPrinter> {
+    let delimiter = char::from(cli.delimiter());
+    if cli.squash_repeats() {
+        Box::new(SquashedRepeats::new(delimiter))
+    } else if let Some(method) = cli.group() {
+        Box::new(GroupedLines::new(method, delimiter))
+    } else if let Some(method) = cli.all_repeated() {
+        Box::new(AllRepeatedLines::new(method, delimiter))
+    } else {
+        Box::new(SelectedGroups::new(
+            cli.count(),
+            !cli.repeated(),
+            !cli.unique(),
+            cli.output_format(),
+            delimiter,
+        ))
+    }
+}
+
+trait GroupPrinter {
+    fn started(&mut self, _group: &LineGroup, _write_buffer: &mut WriteBuffer) -> Result<()> {
+        Ok(())
+    }
+
+    fn repeated(
+        &mut self,
+        _group: &LineGroup,
+        _a_line: &str,
+        _write_buffer: &mut WriteBuffer,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn finished(&mut self, _group: &LineGroup, _write_buffer: &mut WriteBuffer) -> Result<()> {
+        Ok(())
+    }
+
+    fn ended(&mut self, _write_buffer: &mut WriteBuffer) -> Result<()> {
+        Ok(())
+    }
+}
+
+struct SelectedGroups {
+    show_count: bool,
+    show_unique: bool,
+    show_repeated: bool,
+    output_format: OutputFormat,
+    delimiter: char,
+}
+impl SelectedGroups {
+    fn new(
+        show_count: bool,
+        show_unique: bool,
+        show_repeated: bool,
+        output_format: OutputFormat,
+        delimiter: char,
+    ) -> Self {
+        Self {
+            show_count,
+            show_unique,
+            show_repeated,
+            output_format,
+            delimiter,
+        }
+    }
+}
+impl GroupPrinter for SelectedGroups {
+    fn finished(&mut self, group: &LineGroup, write_buffer: &mut WriteBuffer) -> Result<()> {
+        let selected = if group.is_repeated() {
+            self.show_repeated
+        } else {
+            self.show_unique
+        };
+        if selected {
+            write_buffer.write(&match self.output_format {
+                OutputFormat::Text => group.as_string(self.show_count),
+                OutputFormat::Jsonl => group.as_record(self.delimiter).as_jsonl(),
+                OutputFormat::Csv => group.as_record(self.delimiter).as_csv(),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+struct AllRepeatedLines {
+    method: AllRepeatedMethod,
+    delimiter: char,
+    printed_any: bool,
+}
+impl AllRepeatedLines {
+    fn new(method: AllRepeatedMethod, delimiter: char) -> Self {
+        Self {
+            method,
+            delimiter,
+            printed_any: false,
+        }
+    }
+}
+impl GroupPrinter for AllRepeatedLines {
+    fn repeated(
+        &mut self,
+        group: &LineGroup,
+        a_line: &str,
+        write_buffer: &mut WriteBuffer,
+    ) -> Result<()> {
+        if group.count() == 2 {
+            let separated = match self.method {
+                AllRepeatedMethod::None => false,
+                AllRepeatedMethod::Prepend => true,
+                AllRepeatedMethod::Separate => self.printed_any,
+            };
+            if separated {
+                write_buffer.write_char(self.delimiter)?;
+            }
+            write_buffer.write(group.line())?;
+            self.printed_any = true;
+        }
+        write_buffer.write(a_line)
+    }
+}
+
+struct GroupedLines {
+    method: GroupMethod,
+    delimiter: char,
+    printed_any: bool,
+    /// Whether the last line written ended with the delimiter.
+    terminated: bool,
+}
+impl GroupedLines {
+    fn new(method: GroupMethod, delimiter: char) -> Self {
+        Self {
+            method,
+            delimiter,
+            printed_any: false,
+            terminated: true,
+        }
+    }
+
+    fn write_line(&mut self, a_line: &str, write_buffer: &mut WriteBuffer) -> Result<()> {
+        self.terminated = a_line.ends_with(self.delimiter);
+        write_buffer.write(a_line)
+    }
+}
+impl GroupPrinter for GroupedLines {
+    fn started(&mut self, group: &LineGroup, write_buffer: &mut WriteBuffer) -> Result<()> {
+        let separated = match self.method {
+            GroupMethod::Prepend | GroupMethod::Both => true,
+            GroupMethod::Separate | GroupMethod::Append => self.printed_any,
+        };
+        if separated {
+            write_buffer.write_char(self.delimiter)?;
+        }
+        self.printed_any = true;
+        self.write_line(group.line(), write_buffer)
+    }
+
+    fn repeated(
+        &mut self,
+        _group: &LineGroup,
+        a_line: &str,
+        write_buffer: &mut WriteBuffer,
+    ) -> Result<()> {
+        self.write_line(a_line, write_buffer)
+    }
+
+    fn ended(&mut self, write_buffer: &mut WriteBuffer) -> Result<()> {
+        if self.printed_any && matches!(self.method, GroupMethod::Append | GroupMethod::Both) {
+            // An unterminated last line is ended before the separator.
+            if !self.terminated {
+                write_buffer.write_char(self.delimiter)?;
+            }
+            write_buffer.write_char(self.delimiter)?;
+        }
+        Ok(())
+    }
+}
+
+struct SquashedRepeats {
+    delimiter: char,
+}
+impl SquashedRepeats {
+    fn new(delimiter: char) -> Self {
+        Self { delimiter }
+    }
+}
+impl GroupPrinter for SquashedRepeats {
+    fn started(&mut self, group: &LineGroup, write_buffer: &mut WriteBuffer) -> Result<()> {
+        write_buffer.write(group.line())?;
+        write_buffer.flush()
+    }
+
+    fn finished(&mut self, group: &LineGroup, write_buffer: &mut WriteBuffer) -> Result<()> {
+        if group.is_repeated() {
+            write_buffer.write(&format!(
+                "last message repeated {} times{}",
+                group.count() - 1,
+                self.delimiter
+            ))?;
+            write_buffer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+struct LineKey {
+    skip_fields: usize,
+    skip_chars: usize,
+    check_chars: Option<usize>,
+    ignore_case: bool,
+    token_mask: TokenMask,
+    field_key: Option<FieldKey>,
+    delimiter: char,
+}
+impl LineKey {
+    pub fn of(cli: &Cli) -> Self {
+        LineKey {
+            skip_fields: cli.skip_fields(),
+            skip_chars: cli.skip_chars(),
+            check_chars: cli.check_chars(),
+            ignore_case: cli.ignore_case(),
+            token_mask: TokenMask::new(cli.mask_digits(), cli.mask_hex()),
+            field_key: cli
+                .key()
+                .map(|fields| FieldKey::new(fields.clone(), cli.field_delimiter())),
+            delimiter: char::from(cli.delimiter()),
+        }
+    }
+
+    pub fn key<'a>(&self, a_line: &'a str) -> Cow<'a, str> {
+        let mut key = match &self.field_key {
+            Some(field_key) => Cow::Owned(field_key.key(self.record(a_line))),
+            None => Cow::Borrowed(self.selected_chars(self.record(a_line))),
+        };
+        if self.token_mask.is_active() {
+            key = Cow::Owned(self.token_mask.mask(&key));
+        }
+        if self.ignore_case {
+            key = Cow::Owned(key.to_lowercase());
+        }
+        key
+    }
+
+    fn selected_chars<'a>(&self, a_record: &'a str) -> &'a str {
+        let mut key = Self::skip_fields(a_record, self.skip_fields);
+        key = Self::skip_chars(key, self.skip_chars);
+        if let Some(check_chars) = self.check_chars {
+            key = Self::take_chars(key, check_chars);
+        }
+        key
+    }
+
+    pub fn record<'a>(&self, a_line: &'a str) -> &'a str {
+        a_line.strip_suffix(self.delimiter).unwrap_or(a_line)
+    }
+
+    fn skip_fields(a_line: &str, fields: usize) -> &str {
+        let mut rest = a_line;
+        for _ in 0..fields {
+            rest = rest.trim_start_matches(Self::is_blank);
+            rest = rest.trim_start_matches(|c| !Self::is_blank(c));
+        }
+        rest
+    }
+
+    fn skip_chars(a_line: &str, chars: usize) -> &str {
+        match a_line.char_indices().nth(chars) {
+            Some((index, _)) => &a_line[index..],
+            None => "",
+        }
+    }
+
+    fn take_chars(a_line: &str, chars: usize) -> &str {
+        match a_line.char_indices().nth(chars) {
+            Some((index, _)) => &a_line[..index],
+            None => a_line,
+        }
+    }
+
+    fn is_blank(c: char) -> bool {
+        c == ' ' || c == '\t'
+    }
+}
+
+struct LineGroup {
+    a_line: String,
+    key: String,
+    count: usize,
+    first_line_number: usize,
+    last_line_number: usize,
+}
+impl LineGroup {
+    pub fn new() -> Self {
+        LineGroup {
+            a_line: String::new(),
+            key: String::new(),
+            count: 0,
+            first_line_number: 0,
+            last_line_number: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn accepts(&self, a_key: &str, key_matcher: &KeyMatcher) -> bool {
+        !self.is_empty() && key_matcher.matches(&self.key, a_key)
+    }
+
+    pub fn is_repeated(&self) -> bool {
+        self.count > 1
+    }
+
+    pub fn line(&self) -> &str {
+        &self.a_line
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn restart(&mut self, a_line: &str, a_key: &str, a_line_number: usize) {
+        self.a_line = a_line.to_string();
+        self.key = a_key.to_string();
+        self.count = 1;
+        self.first_line_number = a_line_number;
+        self.last_line_number = a_line_number;
+    }
+
+    pub fn add(&mut self, a_line_number: usize) {
+        self.count += 1;
+        self.last_line_number = a_line_number;
+    }
+
+    pub fn as_string(&self, show_count: bool) -> String {
+        if show_count {
+            with_count(self.count, &self.a_line)
+        } else {
+            self.a_line.to_string()
+        }
+    }
+
+    pub fn as_record(&self, delimiter: char) -> GroupRecord<'_> {
+        GroupRecord {
+            a_line: self.a_line.strip_suffix(delimiter).unwrap_or(&self.a_line),
+            count: self.count,
+            first_line_number: self.first_line_number,
+            last_line_number: self.last_line_number,
         }
-        Err(error) => eprintln!("{}: {}", cli.in_file(), error),
     }
-    Ok(())
 }
 
 struct ReadBytes {
@@ -73,20 +907,110 @@ impl ReadBytes {
 }
 struct ReadBuffer {
     raw_buffer: Box<dyn BufRead>,
+    next_filenames: VecDeque<String>,
+    delimiter: u8,
+    raw_record: Vec<u8>,
 }
 impl ReadBuffer {
-    pub fn of(a_filename: &str) -> Result<ReadBuffer> {
+    pub fn of(filenames: &[&str], delimiter: u8) -> Result<ReadBuffer> {
+        let mut next_filenames: VecDeque<String> = filenames
+            .iter()
+            .map(|filename| filename.to_string())
+            .collect();
+        let first_filename = next_filenames
+            .pop_front()
+            .unwrap_or_else(|| "-".to_string());
         Ok(ReadBuffer {
-            raw_buffer: match a_filename {
-                "-" => Box::new(BufReader::new(io::stdin())),
-                _ => Box::new(BufReader::new(File::open(a_filename)?)),
-            },
+            raw_buffer: Self::open(&first_filename)?,
+            next_filenames,
+            delimiter,
+            raw_record: Vec::new(),
         })
     }
 
-    pub fn read_line(&mut self, a_string_to_write: &mut String) -> Result<ReadBytes> {
+    pub fn read_record(&mut self, a_string_to_write: &mut String) -> Result<ReadBytes> {
+        self.raw_record.clear();
+        while self
+            .raw_buffer
+            .read_until(self.delimiter, &mut self.raw_record)?
+            == 0
+        {
+            match self.next_filenames.pop_front() {
+                Some(filename) => self.raw_buffer = Self::open(&filename)?,
+                None => return Ok(ReadBytes { raw_bytes: 0 }),
+            }
+        }
+        // A file without a final delimiter must not glue its last record to
+        // the first record of the next file.
+        if self.raw_record.last() != Some(&self.delimiter) && !self.next_filenames.is_empty() {
+            self.raw_record.push(self.delimiter);
+        }
+        a_string_to_write.push_str(str::from_utf8(&self.raw_record).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )
+        })?);
         Ok(ReadBytes {
-            raw_bytes: self.raw_buffer.read_line(a_string_to_write)?,
+            raw_bytes: self.raw_record.len(),
+        })
+    }
+
+    fn open(a_filename: &str) -> Result<Box<dyn BufRead>> {
+        match a_filename {
+            "-" => Ok(Box::new(BufReader::new(io::stdin()))),
+            _ => Ok(Box::new(BufReader::new(
+                File::open(a_filename).map_err(|e| anyhow!("{a_filename}: {e}"))?,
+            ))),
+        }
+    }
+}
+
+struct WriteBuffer {
+    raw_buffer: Box<dyn Write>,
+}
+impl WriteBuffer {
+    pub fn of(in_filenames: &[&str], an_out_filename: Option<&str>) -> Result<WriteBuffer> {
+        Ok(WriteBuffer {
+            raw_buffer: match an_out_filename {
+                None | Some("-") => Box::new(BufWriter::new(io::stdout())),
+                Some(out_filename) => {
+                    if in_filenames
+                        .iter()
+                        .any(|in_filename| Self::is_same_file(in_filename, out_filename))
+                    {
+                        bail!("{out_filename}: input file is also the output file");
+                    }
+                    let out_file =
+                        File::create(out_filename).map_err(|e| anyhow!("{out_filename}: {e}"))?;
+                    Box::new(BufWriter::new(out_file))
+                }
+            },
         })
     }
+
+    pub fn write(&mut self, a_string: &str) -> Result<()> {
+        Ok(self.raw_buffer.write_all(a_string.as_bytes())?)
+    }
+
+    pub fn write_char(&mut self, a_char: char) -> Result<()> {
+        self.write(a_char.encode_utf8(&mut [0; 4]))
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.raw_buffer.flush()?)
+    }
+
+    fn is_same_file(an_in_filename: &str, an_out_filename: &str) -> bool {
+        if an_in_filename == "-" {
+            return false;
+        }
+        match (
+            fs::canonicalize(an_in_filename),
+            fs::canonicalize(an_out_filename),
+        ) {
+            (Ok(in_path), Ok(out_path)) => in_path == out_path,
+            _ => false,
+        }
+    }
 }
@@ -0,0 +1,72 @@
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Jsonl,
+    Csv,
+}
+
+pub(crate) struct GroupRecord<'a> {
+    pub a_line: &'a str,
+    pub count: usize,
+    pub first_line_number: usize,
+    pub last_line_number: usize,
+}
+impl GroupRecord<'_> {
+    pub fn csv_header() -> &'static str {
+        "line,count,first_line_number,last_line_number\r\n"
+    }
+
+    pub fn as_jsonl(&self) -> String {
+        format!(
+            "{{\"line\":{},\"count\":{},\"first_line_number\":{},\"last_line_number\":{}}}\n",
+            Self::json_string(self.a_line),
+            self.count,
+            self.first_line_number,
+            self.last_line_number
+        )
+    }
+
+    pub fn as_csv(&self) -> String {
+        format!(
+            "{},{},{},{}\r\n",
+            Self::csv_field(self.a_line),
+            self.count,
+            self.first_line_number,
+            self.last_line_number
+        )
+    }
+
+    fn json_string(a_string: &str) -> String {
+        let mut json = String::with_capacity(a_string.len() + 2);
+        json.push('"');
+        for c in a_string.chars() {
+            match c {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                '\u{8}' => json.push_str("\\b"),
+                '\u{c}' => json.push_str("\\f"),
+                c if c < ' ' => {
+                    let _ = write!(json, "\\u{:04x}", c as u32);
+                }
+                c => json.push(c),
+            }
+        }
+        json.push('"');
+        json
+    }
+
+    fn csv_field(a_string: &str) -> String {
+        if a_string.contains([',', '"', '\r', '\n']) {
+            format!("\"{}\"", a_string.replace('"', "\"\""))
+        } else {
+            a_string.to_string()
+        }
+    }
+}
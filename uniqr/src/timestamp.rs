@@ -0,0 +1,148 @@
+use anyhow::{bail, Result};
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Item {
+    Literal(char),
+    Year,
+    Month,
+    MonthName,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    EpochSeconds,
+}
+
+pub(crate) struct TimeFormat {
+    items: Vec<Item>,
+}
+impl TimeFormat {
+    pub fn new(a_pattern: &str) -> Result<Self> {
+        let mut items = Vec::new();
+        let mut chars = a_pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                items.push(Item::Literal(c));
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => items.push(Item::Year),
+                Some('m') => items.push(Item::Month),
+                Some('b') => items.push(Item::MonthName),
+                Some('d' | 'e') => items.push(Item::Day),
+                Some('H') => items.push(Item::Hour),
+                Some('M') => items.push(Item::Minute),
+                Some('S') => items.push(Item::Second),
+                Some('s') => items.push(Item::EpochSeconds),
+                Some('T') => items.extend([
+                    Item::Hour,
+                    Item::Literal(':'),
+                    Item::Minute,
+                    Item::Literal(':'),
+                    Item::Second,
+                ]),
+                Some('F') => items.extend([
+                    Item::Year,
+                    Item::Literal('-'),
+                    Item::Month,
+                    Item::Literal('-'),
+                    Item::Day,
+                ]),
+                Some('%') => items.push(Item::Literal('%')),
+                Some(other) => bail!("unsupported time format specifier \"%{other}\""),
+                None => bail!("time format \"{a_pattern}\" ends with a lone \"%\""),
+            }
+        }
+        Ok(TimeFormat { items })
+    }
+
+    /// Reads a timestamp at the start of the line and returns it as seconds
+    /// together with the rest of the line.
+    pub fn parse<'a>(&self, a_line: &'a str) -> Option<(i64, &'a str)> {
+        let (mut year, mut month, mut day) = (1970, 1, 1);
+        let (mut hour, mut minute, mut second) = (0, 0, 0);
+        let mut epoch_seconds = None;
+        let mut rest = a_line;
+        for item in &self.items {
+            match item {
+                Item::Literal(c) => rest = rest.strip_prefix(*c)?,
+                Item::Year => (year, rest) = Self::number(rest, 4)?,
+                Item::Month => (month, rest) = Self::number(rest, 2)?,
+                Item::Day => (day, rest) = Self::number(rest.trim_start_matches(' '), 2)?,
+                Item::Hour => (hour, rest) = Self::number(rest, 2)?,
+                Item::Minute => (minute, rest) = Self::number(rest, 2)?,
+                Item::Second => (second, rest) = Self::number(rest, 2)?,
+                Item::EpochSeconds => {
+                    let (seconds, after) = Self::number(rest, 19)?;
+                    epoch_seconds = Some(seconds);
+                    rest = after;
+                }
+                Item::MonthName => {
+                    let name = rest.get(..3)?.to_lowercase();
+                    month = MONTHS.iter().position(|m| *m == name)? as i64 + 1;
+                    rest = &rest[3..];
+                }
+            }
+        }
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        let seconds = epoch_seconds.unwrap_or_else(|| {
+            Self::days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second
+        });
+        Some((seconds, rest))
+    }
+
+    fn number(a_text: &str, max_digits: usize) -> Option<(i64, &str)> {
+        let digits = a_text
+            .bytes()
+            .take(max_digits)
+            .take_while(u8::is_ascii_digit)
+            .count();
+        if digits == 0 {
+            return None;
+        }
+        Some((a_text[..digits].parse().ok()?, &a_text[digits..]))
+    }
+
+    // Days since 1970-01-01 in the proleptic Gregorian calendar.
+    fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+}
+
+pub(crate) struct TimeWindow {
+    seconds: i64,
+    time_format: TimeFormat,
+}
+impl TimeWindow {
+    pub fn new(seconds: u64, time_format: TimeFormat) -> Self {
+        TimeWindow {
+            seconds: seconds as i64,
+            time_format,
+        }
+    }
+
+    pub fn split<'a>(&self, a_line: &'a str) -> (Option<i64>, &'a str) {
+        match self.time_format.parse(a_line) {
+            Some((seconds, rest)) => (Some(seconds), rest),
+            None => (None, a_line),
+        }
+    }
+
+    pub fn contains(&self, previous: Option<i64>, current: Option<i64>) -> bool {
+        match (previous, current) {
+            (Some(previous), Some(current)) => current - previous <= self.seconds,
+            _ => true,
+        }
+    }
+}
@@ -3,7 +3,7 @@ use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
 use rand::{distributions::Alphanumeric, Rng};
-use std::fs;
+use std::{fs, io::Write};
 use tempfile::NamedTempFile;
 
 struct Test {
@@ -108,6 +108,37 @@ fn dies_bad_file() -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn dies_bad_outfile() -> Result<()> {
+    let bad = format!("{}/out.txt", gen_bad_file());
+    let expected = format!("{bad}: .* [(]os error 2[)]");
+    Command::cargo_bin(PRG)?
+        .args([T1.input, &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_outfile_is_infile() -> Result<()> {
+    let outfile = NamedTempFile::new()?;
+    let outpath = outfile.path().to_str().unwrap();
+    fs::write(outpath, "a\na\n")?;
+
+    Command::cargo_bin(PRG)?
+        .args([outpath, outpath])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "input file is also the output file",
+        ));
+    assert_eq!(fs::read_to_string(outpath)?, "a\na\n");
+    Ok(())
+}
+
 // --------------------------------------------------
 // HELPER FUNCTIONS
 fn run(test: &Test) -> Result<()> {
@@ -617,3 +648,586 @@ fn t6_outfile_count() -> Result<()> {
 fn t6_stdin_outfile_count() -> Result<()> {
     run_stdin_outfile_count(&T6)
 }
+
+// --------------------------------------------------
+fn run_args(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read_to_string(expected_file)?;
+    let output = Command::cargo_bin(PRG)?.args(args).output().expect("fail");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("invalid UTF-8");
+    assert_eq!(stdout, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn three_repeated() -> Result<()> {
+    run_args(&[THREE.input, "-d"], "tests/expected/three.txt.d.out")
+}
+
+#[test]
+fn three_repeated_count() -> Result<()> {
+    run_args(
+        &[THREE.input, "-c", "-d"],
+        "tests/expected/three.txt.cd.out",
+    )
+}
+
+#[test]
+fn three_unique() -> Result<()> {
+    run_args(&[THREE.input, "-u"], "tests/expected/three.txt.u.out")
+}
+
+#[test]
+fn three_unique_count() -> Result<()> {
+    run_args(
+        &[THREE.input, "-c", "-u"],
+        "tests/expected/three.txt.cu.out",
+    )
+}
+
+#[test]
+fn three_all_repeated() -> Result<()> {
+    run_args(&[THREE.input, "-D"], "tests/expected/three.txt.D.out")?;
+    run_args(
+        &[THREE.input, "--all-repeated"],
+        "tests/expected/three.txt.D.out",
+    )
+}
+
+#[test]
+fn three_all_repeated_prepend() -> Result<()> {
+    run_args(
+        &[THREE.input, "--all-repeated=prepend"],
+        "tests/expected/three.txt.all-repeated-prepend.out",
+    )
+}
+
+#[test]
+fn three_all_repeated_separate() -> Result<()> {
+    run_args(
+        &[THREE.input, "--all-repeated=separate"],
+        "tests/expected/three.txt.all-repeated-separate.out",
+    )
+}
+
+#[test]
+fn three_group() -> Result<()> {
+    run_args(
+        &[THREE.input, "--group"],
+        "tests/expected/three.txt.group-separate.out",
+    )?;
+    for method in ["separate", "prepend", "append", "both"] {
+        run_args(
+            &[THREE.input, &format!("--group={method}")],
+            &format!("tests/expected/three.txt.group-{method}.out"),
+        )?;
+    }
+    Ok(())
+}
+
+#[test]
+fn no_newline_group_appended() -> Result<()> {
+    for method in ["append", "both"] {
+        run_args(
+            &["tests/inputs/no-newline.txt", &format!("--group={method}")],
+            &format!("tests/expected/no-newline.txt.group-{method}.out"),
+        )?;
+    }
+    Ok(())
+}
+
+#[test]
+fn dies_all_repeated_count() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "-D", "-c"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+#[test]
+fn dies_group_unique() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "--group", "-u"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+const LOGS: &str = "tests/inputs/logs.txt";
+
+#[test]
+fn logs_skip_fields() -> Result<()> {
+    run_args(&[LOGS, "-c", "-f", "2"], "tests/expected/logs.txt.f2.out")
+}
+
+#[test]
+fn logs_skip_fields_ignore_case() -> Result<()> {
+    run_args(
+        &[LOGS, "-c", "--skip-fields", "2", "-i"],
+        "tests/expected/logs.txt.f2i.out",
+    )
+}
+
+#[test]
+fn logs_skip_chars_check_chars() -> Result<()> {
+    run_args(
+        &[LOGS, "-c", "-s", "20", "-w", "4"],
+        "tests/expected/logs.txt.s20w4.out",
+    )
+}
+
+#[test]
+fn logs_check_chars() -> Result<()> {
+    run_args(
+        &[LOGS, "-c", "--check-chars", "10"],
+        "tests/expected/logs.txt.w10.out",
+    )
+}
+
+// --------------------------------------------------
+const UNSORTED: &str = "tests/inputs/unsorted.txt";
+
+#[test]
+fn unsorted_global() -> Result<()> {
+    run_args(
+        &[UNSORTED, "--global"],
+        "tests/expected/unsorted.txt.global.out",
+    )
+}
+
+#[test]
+fn unsorted_global_spills() -> Result<()> {
+    for max_memory in ["64", "128", "192", "256", "320", "384"] {
+        run_args(
+            &[UNSORTED, "--global", "--max-memory", max_memory],
+            "tests/expected/unsorted.txt.global.out",
+        )?;
+    }
+    Ok(())
+}
+
+#[test]
+fn stdin_global_spills_many_runs() -> Result<()> {
+    let lines: Vec<String> = (0..20_000u64)
+        .map(|n| format!("line {}", n * 7_919 % 3_001))
+        .collect();
+    let mut seen = std::collections::HashSet::new();
+    let expected: String = lines
+        .iter()
+        .filter(|line| seen.insert(line.as_str()))
+        .map(|line| format!("{line}\n"))
+        .collect();
+    let input = lines.join("\n") + "\n";
+    for max_memory in ["64", "320", "1000", "10K"] {
+        let output = Command::cargo_bin(PRG)?
+            .args(["--global", "--max-memory", max_memory])
+            .write_stdin(input.as_str())
+            .output()?;
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout)?, expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn dies_global_bad_max_memory() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([UNSORTED, "--global", "--max-memory", "12X"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid size \"12X\""));
+    Ok(())
+}
+
+#[test]
+fn dies_global_count() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([UNSORTED, "--global", "-c"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+#[test]
+fn unsorted_freq() -> Result<()> {
+    run_args(
+        &[UNSORTED, "--freq"],
+        "tests/expected/unsorted.txt.freq.out",
+    )
+}
+
+#[test]
+fn unsorted_freq_top() -> Result<()> {
+    run_args(
+        &[UNSORTED, "--freq", "--top", "2"],
+        "tests/expected/unsorted.txt.freq.top2.out",
+    )
+}
+
+#[test]
+fn unsorted_freq_min_count_percent() -> Result<()> {
+    run_args(
+        &[UNSORTED, "--freq", "--min-count", "2", "--percent"],
+        "tests/expected/unsorted.txt.freq.mincount2percent.out",
+    )
+}
+
+#[test]
+fn dies_top_without_freq() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([UNSORTED, "--top", "2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--freq"));
+    Ok(())
+}
+
+// --------------------------------------------------
+const SYSLOG: &str = "tests/inputs/syslog.txt";
+
+#[test]
+fn syslog_squash_repeats() -> Result<()> {
+    run_args(
+        &[SYSLOG, "--squash-repeats"],
+        "tests/expected/syslog.txt.squash.out",
+    )
+}
+
+#[test]
+fn syslog_squash_repeats_skip_fields() -> Result<()> {
+    run_args(
+        &[SYSLOG, "--squash-repeats", "-f", "3"],
+        "tests/expected/syslog.txt.squash.f3.out",
+    )
+}
+
+#[test]
+fn syslog_squash_repeats_window() -> Result<()> {
+    run_args(
+        &[SYSLOG, "--squash-repeats", "--window", "10"],
+        "tests/expected/syslog.txt.squash.window10.out",
+    )
+}
+
+#[test]
+fn squash_repeats_flushes_eagerly() -> Result<()> {
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .arg("--squash-repeats")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = std::io::BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+
+    writeln!(stdin, "a")?;
+    std::io::BufRead::read_line(&mut stdout, &mut line)?;
+    assert_eq!(line, "a\n");
+
+    writeln!(stdin, "a")?;
+    writeln!(stdin, "b")?;
+    line.clear();
+    std::io::BufRead::read_line(&mut stdout, &mut line)?;
+    assert_eq!(line, "last message repeated 1 times\n");
+
+    drop(stdin);
+    assert!(child.wait()?.success());
+    Ok(())
+}
+
+#[test]
+fn dies_bad_time_format() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([SYSLOG, "--squash-repeats", "--window", "5"])
+        .args(["--time-format", "%Q"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unsupported time format"));
+    Ok(())
+}
+
+#[test]
+fn unsorted_count_distinct() -> Result<()> {
+    run_args(
+        &[UNSORTED, "--count-distinct"],
+        "tests/expected/unsorted.txt.count-distinct.out",
+    )
+}
+
+#[test]
+fn unsorted_count_distinct_exact() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([UNSORTED, "--count-distinct", "--exact"])
+        .assert()
+        .success()
+        .stdout("6\n");
+    Ok(())
+}
+
+#[test]
+fn count_distinct_merges_shards() -> Result<()> {
+    let sketch = NamedTempFile::new()?;
+    let sketch_path = sketch.path().to_str().unwrap();
+
+    Command::cargo_bin(PRG)?
+        .args(["--count-distinct", "--save-sketch", sketch_path])
+        .write_stdin("b\na\nb\nc\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("3\t"));
+
+    Command::cargo_bin(PRG)?
+        .args(["--count-distinct", "--merge-sketch", sketch_path])
+        .write_stdin("a\nd\nc\ne\nb\nf\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("6\t"));
+    Ok(())
+}
+
+#[test]
+fn dies_merge_sketch_other_precision() -> Result<()> {
+    let sketch = NamedTempFile::new()?;
+    let sketch_path = sketch.path().to_str().unwrap();
+
+    Command::cargo_bin(PRG)?
+        .args([UNSORTED, "--count-distinct", "--precision", "10"])
+        .args(["--save-sketch", sketch_path])
+        .assert()
+        .success();
+
+    Command::cargo_bin(PRG)?
+        .args([UNSORTED, "--count-distinct", "--merge-sketch", sketch_path])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "cannot merge a sketch of precision 10",
+        ));
+    Ok(())
+}
+
+#[test]
+fn dies_merge_sketch_bad_file() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([UNSORTED, "--count-distinct", "--merge-sketch", UNSORTED])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a uniqr sketch"));
+    Ok(())
+}
+
+#[test]
+fn dies_count_distinct_bad_precision() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([UNSORTED, "--count-distinct", "--precision", "19"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("4..=18"));
+    Ok(())
+}
+
+// --------------------------------------------------
+const FUZZY: &str = "tests/inputs/fuzzy.txt";
+
+#[test]
+fn fuzzy_levenshtein_count() -> Result<()> {
+    run_args(
+        &[FUZZY, "-c", "--fuzzy"],
+        "tests/expected/fuzzy.txt.c.levenshtein.out",
+    )
+}
+
+#[test]
+fn fuzzy_jaccard_count() -> Result<()> {
+    run_args(
+        &[FUZZY, "-c", "--fuzzy=jaccard", "--threshold", "0.5"],
+        "tests/expected/fuzzy.txt.c.jaccard.out",
+    )
+}
+
+#[test]
+fn fuzzy_all_repeated() -> Result<()> {
+    run_args(
+        &[FUZZY, "-D", "--fuzzy", "--threshold", "0.6"],
+        "tests/expected/fuzzy.txt.D.levenshtein.out",
+    )
+}
+
+#[test]
+fn mask_digits_and_hex_count() -> Result<()> {
+    run_args(
+        &[FUZZY, "-c", "--mask-digits", "--mask-hex"],
+        "tests/expected/fuzzy.txt.c.mask.out",
+    )
+}
+
+#[test]
+fn dies_bad_threshold() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([FUZZY, "--fuzzy", "--threshold", "1.5"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid threshold \"1.5\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files_groups_span_file_boundaries() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "--files", T2.input, EMPTY.input, T1.input])
+        .assert()
+        .success()
+        .stdout("   4 a\n");
+    Ok(())
+}
+
+#[test]
+fn files_missing_final_newline() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "--files", T3.input, T5.input])
+        .assert()
+        .success()
+        .stdout("   1 a\n   2 b\n   2 a\n");
+    Ok(())
+}
+
+#[test]
+fn dies_files_bad_file() -> Result<()> {
+    let bad = gen_bad_file();
+    let expected = format!("{bad}: .* [(]os error 2[)]");
+    Command::cargo_bin(PRG)?
+        .args(["--files", T1.input, &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+#[test]
+fn zero_terminated_count() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-z", "-c"])
+        .write_stdin("a\0a\0b\nc\0")
+        .assert()
+        .success()
+        .stdout("   2 a\0   1 b\nc\0");
+    Ok(())
+}
+
+#[test]
+fn zero_terminated_group() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--zero-terminated", "--group"])
+        .write_stdin("a\0a\0b\0")
+        .assert()
+        .success()
+        .stdout("a\0a\0\0b\0");
+    Ok(())
+}
+
+// --------------------------------------------------
+const RECORDS: &str = "tests/inputs/records.txt";
+
+#[test]
+fn records_jsonl() -> Result<()> {
+    run_args(
+        &[RECORDS, "--output-format", "jsonl"],
+        "tests/expected/records.txt.jsonl.out",
+    )
+}
+
+#[test]
+fn records_csv() -> Result<()> {
+    run_args(
+        &[RECORDS, "--output-format", "csv"],
+        "tests/expected/records.txt.csv.out",
+    )
+}
+
+#[test]
+fn records_repeated_jsonl() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([RECORDS, "-d", "--output-format", "jsonl"])
+        .assert()
+        .success()
+        .stdout("{\"line\":\"a\",\"count\":2,\"first_line_number\":1,\"last_line_number\":2}\n");
+    Ok(())
+}
+
+#[test]
+fn unsorted_freq_csv() -> Result<()> {
+    run_args(
+        &[UNSORTED, "--freq", "--output-format", "csv"],
+        "tests/expected/unsorted.txt.freq.csv.out",
+    )
+}
+
+#[test]
+fn dies_group_output_format() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([RECORDS, "--group", "--output-format", "jsonl"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+const CUSTOMERS: &str = "tests/inputs/customers.csv";
+
+#[test]
+fn customers_key() -> Result<()> {
+    run_args(
+        &[CUSTOMERS, "-c", "-k", "1"],
+        "tests/expected/customers.csv.k1.out",
+    )
+}
+
+#[test]
+fn customers_key_ranges_ignore_case() -> Result<()> {
+    run_args(
+        &[CUSTOMERS, "-c", "--key", "2,4-5", "-i"],
+        "tests/expected/customers.csv.key-ranges.out",
+    )
+}
+
+#[test]
+fn tab_delimited_key() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "-k", "2", "-t", "\t"])
+        .write_stdin("a\tx\tone\nb\tx\ttwo\nc\ty\tthree\n")
+        .assert()
+        .success()
+        .stdout("   2 a\tx\tone\n   1 c\ty\tthree\n");
+    Ok(())
+}
+
+#[test]
+fn dies_bad_key() -> Result<()> {
+    for bad in ["0", "3-2", "a", "1,"] {
+        Command::cargo_bin(PRG)?
+            .args([CUSTOMERS, "-k", bad])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("illegal field value"));
+    }
+    Ok(())
+}
+
+#[test]
+fn dies_key_skip_fields() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([CUSTOMERS, "-k", "1", "-f", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}